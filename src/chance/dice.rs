@@ -0,0 +1,926 @@
+/*!
+Tabletop-style dice notation, like `3d6+2`.
+
+Parse a [`DiceExpr`] from a string, then [roll](DiceExpr::roll) it or figure out its exact
+[distribution](DiceExpr::distribution).
+
+The notation supported is:
+
+- `NdS`: roll `N` dice with `S` sides and add them up. `N` can be left off to roll just one.
+- `d%`: the same as `d100`.
+- `NdS!`: exploding dice. Whenever a die rolls its highest number, roll it again and add that on too.
+- `NdSkhK` and `NdSklK`: only keep the highest (or lowest) `K` dice.
+- Plain numbers.
+- Any number of the above added or subtracted together with `+` and `-`.
+
+You can roll at most [`MAX_DICE`] dice at once, with at most [`MAX_SIDES`] sides each.
+Keeping dice is limited more, because working out the distribution gets slow fast:
+something like `100d20kh50` is fine, but `50d1000kh25` isn't.
+
+Whitespace is ignored, and it's case-insensitive.
+
+```
+# use cogs_gamedev::chance::dice::DiceExpr;
+// Roll 4 six-sided dice and keep the highest 3, the classic way to make D&D stats
+let stat: DiceExpr = "4d6kh3".parse().unwrap();
+let roll = stat.roll(&mut rand::thread_rng());
+assert!((3..=18).contains(&roll.total));
+assert_eq!(roll.terms[0].dice.len(), 4);
+assert_eq!(roll.terms[0].dice.iter().filter(|die| die.kept).count(), 3);
+
+let odds = stat.distribution();
+assert!((odds.probability(18) - 21.0 / 1296.0).abs() < 1e-12);
+assert!((odds.mean() - 12.24).abs() < 0.01);
+```
+*/
+
+use rand::Rng;
+
+use std::{fmt::Display, str::FromStr};
+
+/// Exploding dice stop after this many explosions when rolling.
+/// (With at least 2 sides, this is astronomically unlikely to matter.)
+const MAX_EXPLOSIONS: usize = 100;
+
+/// When working out a distribution, explosions that are less likely than this are ignored.
+const EXPLOSION_CUTOFF: f64 = 1e-12;
+
+/// The most dice a [`DiceRoll`] can roll at once.
+pub const MAX_DICE: u32 = 100;
+
+/// The most sides a die in a [`DiceRoll`] can have.
+pub const MAX_SIDES: u32 = 1000;
+
+/// Keeping dice with `kh` or `kl` can't take more steps than this to work out the distribution.
+const MAX_KEEP_WORK: f64 = 1e8;
+
+/// A parsed dice expression: a bunch of terms added or subtracted together.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiceExpr {
+    pub terms: Vec<(Sign, Term)>,
+}
+
+/// Whether a [`Term`] is added or subtracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sign {
+    Plus,
+    Minus,
+}
+
+/// One part of a [`DiceExpr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Term {
+    Dice(DiceRoll),
+    Constant(u32),
+}
+
+/// Some dice to roll, like `4d6kh3`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DiceRoll {
+    count: u32,
+    sides: u32,
+    exploding: bool,
+    keep: Option<Keep>,
+}
+
+/// Which dice to keep out of a [`DiceRoll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+/// Problems that can happen parsing a [`DiceExpr`].
+///
+/// Positions are indices of `char`s in the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiceParseError {
+    /// The string ended when something else was expected.
+    UnexpectedEnd,
+    /// There was a character that didn't make sense there.
+    UnexpectedChar { pos: usize, found: char },
+    /// A number didn't fit in a `u32`.
+    NumberTooBig { pos: usize },
+    /// Something like `0d6`.
+    ZeroDice { pos: usize },
+    /// Something like `3d0`.
+    ZeroSides { pos: usize },
+    /// Something like `2d6kh3`, keeping more dice than were rolled.
+    KeepTooMany { pos: usize, keep: u32, count: u32 },
+    /// Something like `1d1!`, which would explode forever.
+    InfiniteExplosion { pos: usize },
+    /// Rolling more than [`MAX_DICE`] dice at once.
+    TooManyDice { pos: usize },
+    /// A die with more than [`MAX_SIDES`] sides.
+    TooManySides { pos: usize },
+    /// Something like `50d1000kh25`, keeping so many dice out of so many
+    /// that working out the distribution would take far too long.
+    KeepTooSlow { pos: usize },
+}
+
+impl Display for DiceParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiceParseError::UnexpectedEnd => write!(f, "unexpected end of dice expression"),
+            DiceParseError::UnexpectedChar { pos, found } => {
+                write!(f, "unexpected {:?} at {} in dice expression", found, pos)
+            }
+            DiceParseError::NumberTooBig { pos } => {
+                write!(f, "number at {} in dice expression is too big", pos)
+            }
+            DiceParseError::ZeroDice { pos } => write!(f, "can't roll zero dice (at {})", pos),
+            DiceParseError::ZeroSides { pos } => {
+                write!(f, "can't roll dice with zero sides (at {})", pos)
+            }
+            DiceParseError::KeepTooMany { pos, keep, count } => {
+                write!(f, "can't keep {} dice out of {} (at {})", keep, count, pos)
+            }
+            DiceParseError::InfiniteExplosion { pos } => {
+                write!(f, "one-sided dice can't explode (at {})", pos)
+            }
+            DiceParseError::TooManyDice { pos } => {
+                write!(f, "can't roll more than {} dice (at {})", MAX_DICE, pos)
+            }
+            DiceParseError::TooManySides { pos } => {
+                write!(
+                    f,
+                    "dice can't have more than {} sides (at {})",
+                    MAX_SIDES, pos
+                )
+            }
+            DiceParseError::KeepTooSlow { pos } => {
+                write!(f, "too many dice to keep track of (at {})", pos)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiceParseError {}
+
+/**
+The result of rolling a [`DiceExpr`].
+
+```
+# use cogs_gamedev::chance::dice::{DiceExpr, Sign};
+let expr: DiceExpr = "2d8 - 1".parse().unwrap();
+let roll = expr.roll(&mut rand::thread_rng());
+
+assert_eq!(roll.terms.len(), 2);
+assert_eq!(roll.terms[0].dice.len(), 2);
+assert_eq!(roll.terms[1].sign, Sign::Minus);
+assert_eq!(roll.terms[1].value, 1);
+assert_eq!(roll.total, roll.terms[0].value - 1);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RollResult {
+    pub total: i64,
+    /// The results of each term, in the same order as in the expression.
+    pub terms: Vec<TermResult>,
+}
+
+/// The result of rolling one [`Term`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TermResult {
+    pub sign: Sign,
+    /// What the term came out to, before its sign is applied.
+    pub value: i64,
+    /// Each die that was rolled, in order. This is empty for constants.
+    pub dice: Vec<Die>,
+}
+
+/// One die that was rolled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Die {
+    /// Each number rolled on this die. There's only more than one if it exploded.
+    pub rolls: Vec<u32>,
+    /// Whether this die counted towards the total.
+    pub kept: bool,
+}
+
+impl Die {
+    /// The total of this die, including any explosions.
+    pub fn value(&self) -> u32 {
+        self.rolls.iter().sum()
+    }
+}
+
+impl DiceExpr {
+    /// Roll the dice.
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> RollResult {
+        let terms: Vec<_> = self
+            .terms
+            .iter()
+            .map(|(sign, term)| match term {
+                Term::Constant(value) => TermResult {
+                    sign: *sign,
+                    value: *value as i64,
+                    dice: Vec::new(),
+                },
+                Term::Dice(roll) => {
+                    let dice = roll.roll(rng);
+                    TermResult {
+                        sign: *sign,
+                        value: dice
+                            .iter()
+                            .filter(|die| die.kept)
+                            .map(|die| die.value() as i64)
+                            .sum(),
+                        dice,
+                    }
+                }
+            })
+            .collect();
+        let total = terms
+            .iter()
+            .map(|term| match term.sign {
+                Sign::Plus => term.value,
+                Sign::Minus => -term.value,
+            })
+            .sum();
+        RollResult { total, terms }
+    }
+
+    /// Work out the exact probability of every possible total.
+    ///
+    /// Exploding dice can roll arbitrarily high, so their distributions are cut off once
+    /// more explosions become less likely than one in a trillion.
+    ///
+    /// The limits on how many dice you can roll and keep make sure this doesn't take too long.
+    pub fn distribution(&self) -> Distribution {
+        let mut out = Distribution::constant(0);
+        for (sign, term) in self.terms.iter() {
+            let dist = match term {
+                Term::Constant(value) => Distribution::constant(*value as i64),
+                Term::Dice(roll) => roll.distribution(),
+            };
+            let dist = match sign {
+                Sign::Plus => dist,
+                Sign::Minus => dist.negate(),
+            };
+            out = out.add(&dist);
+        }
+        out
+    }
+}
+
+impl DiceRoll {
+    /// Make some dice to roll.
+    ///
+    /// Returns `None` for dice that don't make sense, the same ones that would fail to parse:
+    /// no dice or no sides, more than [`MAX_DICE`] dice or [`MAX_SIDES`] sides,
+    /// exploding one-sided dice, keeping more dice than are rolled,
+    /// or keeping so many dice out of so many that the distribution would be too slow to work out.
+    ///
+    /// ```
+    /// # use cogs_gamedev::chance::dice::{DiceRoll, Keep};
+    /// let stat = DiceRoll::new(4, 6, false, Some(Keep::Highest(3))).unwrap();
+    /// assert_eq!(stat.to_string(), "4d6kh3");
+    ///
+    /// assert_eq!(DiceRoll::new(1, 1, true, None), None);
+    /// assert_eq!(DiceRoll::new(3, 0, false, None), None);
+    /// assert_eq!(DiceRoll::new(50, 1000, false, Some(Keep::Highest(25))), None);
+    /// ```
+    pub fn new(count: u32, sides: u32, exploding: bool, keep: Option<Keep>) -> Option<Self> {
+        let kept = match keep {
+            Some(Keep::Highest(kept)) | Some(Keep::Lowest(kept)) => kept,
+            None => count,
+        };
+        if (1..=MAX_DICE).contains(&count)
+            && (1..=MAX_SIDES).contains(&sides)
+            && !(exploding && sides == 1)
+            && kept <= count
+            && keep_work(count, sides, exploding, kept) <= MAX_KEEP_WORK
+        {
+            Some(Self {
+                count,
+                sides,
+                exploding,
+                keep,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// How many dice to roll.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// How many sides each die has.
+    pub fn sides(&self) -> u32 {
+        self.sides
+    }
+
+    /// Whether rolling the highest number rolls the die again.
+    pub fn exploding(&self) -> bool {
+        self.exploding
+    }
+
+    /// Which dice to keep, or `None` to keep all of them.
+    pub fn keep(&self) -> Option<Keep> {
+        self.keep
+    }
+
+    /// Roll these dice.
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Die> {
+        let mut dice: Vec<_> = (0..self.count)
+            .map(|_| {
+                let mut rolls = vec![rng.gen_range(1..=self.sides)];
+                while self.exploding
+                    && *rolls.last().unwrap() == self.sides
+                    && rolls.len() <= MAX_EXPLOSIONS
+                {
+                    rolls.push(rng.gen_range(1..=self.sides));
+                }
+                Die { rolls, kept: true }
+            })
+            .collect();
+
+        if let Some(keep) = self.keep {
+            let mut order: Vec<usize> = (0..dice.len()).collect();
+            let kept = match keep {
+                Keep::Highest(kept) => {
+                    order.sort_by_key(|&idx| std::cmp::Reverse(dice[idx].value()));
+                    kept
+                }
+                Keep::Lowest(kept) => {
+                    order.sort_by_key(|&idx| dice[idx].value());
+                    kept
+                }
+            };
+            for &idx in order.iter().skip(kept as usize) {
+                dice[idx].kept = false;
+            }
+        }
+        dice
+    }
+
+    /// Work out the exact probability of every possible total of these dice.
+    pub fn distribution(&self) -> Distribution {
+        let runs = face_runs(self.sides, self.exploding);
+        let (kept, highest_first) = match self.keep {
+            Some(Keep::Highest(kept)) => (kept, true),
+            Some(Keep::Lowest(kept)) => (kept, false),
+            None => (self.count, false),
+        };
+
+        // Keeping all of them is the same as not keeping any
+        if kept == self.count {
+            let mut out = Distribution::constant(0);
+            for _ in 0..self.count {
+                out = out.add_runs(&runs);
+            }
+            return out;
+        }
+
+        let mut faces: Vec<(i64, f64)> = runs
+            .iter()
+            .flat_map(|&(lowest, len, chance)| {
+                (lowest..lowest + len as i64).map(move |face| (face, chance))
+            })
+            .collect();
+        if highest_first {
+            faces.reverse();
+        }
+        keep_distribution(&faces, self.count as usize, kept as usize)
+    }
+}
+
+/// The faces of one die, in runs of equally likely values: (lowest, how many, chance of each).
+fn face_runs(sides: u32, exploding: bool) -> Vec<(i64, u32, f64)> {
+    let per_side = 1.0 / sides as f64;
+    let mut runs = Vec::new();
+    if exploding {
+        let mut chain_chance = 1.0;
+        let mut base = 0i64;
+        while chain_chance * per_side > EXPLOSION_CUTOFF {
+            runs.push((base + 1, sides - 1, chain_chance * per_side));
+            chain_chance *= per_side;
+            base += sides as i64;
+        }
+    } else {
+        runs.push((1, sides, per_side));
+    }
+    runs
+}
+
+/// Roughly how many steps [`keep_distribution`] takes to keep `kept` of these dice.
+fn keep_work(count: u32, sides: u32, exploding: bool, kept: u32) -> f64 {
+    if kept == count || sides == 0 || (exploding && sides == 1) {
+        return 0.0;
+    }
+    let runs = face_runs(sides, exploding);
+    let faces: f64 = runs.iter().map(|&(_, len, _)| len as f64).sum();
+    let highest = runs
+        .last()
+        .map_or(0.0, |&(lowest, len, _)| (lowest + len as i64 - 1) as f64);
+    let kept = kept as f64;
+    // For each face, it goes over the totals for each number of kept dice so far,
+    // once for each number of dice that could land on that face
+    faces * highest * kept * kept * kept / 6.0 + faces * kept * count as f64
+}
+
+/// Work out the distribution of the sum of the first `kept` dice out of `count`,
+/// given the faces in order of which get kept first.
+fn keep_distribution(faces: &[(i64, f64)], count: usize, kept: usize) -> Distribution {
+    if kept == 0 {
+        return Distribution::constant(0);
+    }
+    let mut choose = vec![vec![1.0f64; count + 1]; count + 1];
+    for n in 1..=count {
+        for k in 1..n {
+            choose[n][k] = choose[n - 1][k - 1] + choose[n - 1][k];
+        }
+    }
+    // after[idx] is the chance of a die landing on a face from `idx` onwards
+    let mut after = vec![0.0; faces.len() + 1];
+    for idx in (0..faces.len()).rev() {
+        after[idx] = after[idx + 1] + faces[idx].1;
+    }
+
+    // states[assigned][total] is the probability of the first `assigned` dice adding up to `total`,
+    // after deciding what landed on the faces so far. Because faces come in keeping order,
+    // those are all kept, and once `kept` dice have landed the total can't change any more,
+    // so it goes straight into `out`.
+    let highest = faces.iter().map(|&(face, _)| face).max().unwrap_or(0) as usize;
+    let totals = kept * highest + 1;
+    let mut states = vec![vec![0.0; totals]; kept];
+    let mut next = states.clone();
+    let mut out = vec![0.0; totals];
+    states[0][0] = 1.0;
+    for (idx, &(face, chance)) in faces.iter().enumerate() {
+        for row in next.iter_mut() {
+            row.iter_mut().for_each(|p| *p = 0.0);
+        }
+        for (assigned, row) in states.iter().enumerate() {
+            let row = &row[..assigned * highest + 1];
+            let left = count - assigned;
+            // The chance of enough dice landing here to fill up the kept ones,
+            // with all the others landing on later faces
+            let mut finishing = 0.0;
+            let mut power = 1.0;
+            for with_face in 0..=left {
+                let weight = choose[left][with_face] * power;
+                if assigned + with_face < kept {
+                    let shift = face as usize * with_face;
+                    for (total, &p) in row.iter().enumerate() {
+                        if p != 0.0 {
+                            next[assigned + with_face][total + shift] += p * weight;
+                        }
+                    }
+                } else {
+                    finishing += weight * after[idx + 1].powi((left - with_face) as i32);
+                }
+                power *= chance;
+            }
+            let shift = face as usize * (kept - assigned);
+            for (total, &p) in row.iter().enumerate() {
+                if p != 0.0 {
+                    out[total + shift] += p * finishing;
+                }
+            }
+        }
+        std::mem::swap(&mut states, &mut next);
+    }
+
+    let min = out.iter().position(|&p| p != 0.0).unwrap_or(0);
+    let max = out.iter().rposition(|&p| p != 0.0).unwrap_or(0);
+    Distribution {
+        min: min as i64,
+        probabilities: out[min..=max].to_vec(),
+    }
+}
+
+/**
+The probability of every possible total of some dice.
+
+```
+# use cogs_gamedev::chance::dice::DiceExpr;
+let expr: DiceExpr = "2d6".parse().unwrap();
+let odds = expr.distribution();
+assert_eq!((odds.min(), odds.max()), (2, 12));
+// Floating-point math isn't exact, so these are all approximately equal
+assert!((odds.probability(7) - 6.0 / 36.0).abs() < 1e-12);
+assert!((odds.at_least(11) - 3.0 / 36.0).abs() < 1e-12);
+assert!((odds.mean() - 7.0).abs() < 1e-12);
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    min: i64,
+    /// `probabilities[i]` is the chance of getting `min + i`
+    probabilities: Vec<f64>,
+}
+
+impl Distribution {
+    fn constant(value: i64) -> Self {
+        Self {
+            min: value,
+            probabilities: vec![1.0],
+        }
+    }
+
+    fn negate(&self) -> Self {
+        Self {
+            min: -self.max(),
+            probabilities: self.probabilities.iter().rev().copied().collect(),
+        }
+    }
+
+    /// The distribution of the sum of something from this and something from `other`.
+    fn add(&self, other: &Distribution) -> Self {
+        let mut probabilities = vec![0.0; self.probabilities.len() + other.probabilities.len() - 1];
+        for (i, a) in self.probabilities.iter().enumerate() {
+            for (j, b) in other.probabilities.iter().enumerate() {
+                probabilities[i + j] += a * b;
+            }
+        }
+        Self {
+            min: self.min + other.min,
+            probabilities,
+        }
+    }
+
+    /// The distribution of the sum of something from this and one die,
+    /// whose faces come in runs of equally likely values.
+    ///
+    /// Each run gets added on with a difference array, so this only takes as long as
+    /// the number of runs times the length of the output.
+    fn add_runs(&self, runs: &[(i64, u32, f64)]) -> Self {
+        let lowest = runs[0].0;
+        let highest = runs
+            .iter()
+            .map(|&(start, len, _)| start + len as i64 - 1)
+            .max()
+            .unwrap();
+        let len = self.probabilities.len() + (highest - lowest) as usize;
+        let mut diffs = vec![0.0; len + 1];
+        for (i, &p) in self.probabilities.iter().enumerate() {
+            for &(start, run_len, chance) in runs {
+                let offset = i + (start - lowest) as usize;
+                diffs[offset] += p * chance;
+                diffs[offset + run_len as usize] -= p * chance;
+            }
+        }
+        let mut running = 0.0;
+        let probabilities = diffs[..len]
+            .iter()
+            .map(|diff| {
+                running += diff;
+                // Rounding error shouldn't make anything negative
+                f64::max(running, 0.0)
+            })
+            .collect();
+        Self {
+            min: self.min + lowest,
+            probabilities,
+        }
+    }
+
+    /// The lowest possible total.
+    pub fn min(&self) -> i64 {
+        self.min
+    }
+
+    /// The highest possible total.
+    pub fn max(&self) -> i64 {
+        self.min + self.probabilities.len() as i64 - 1
+    }
+
+    /// The chance of getting exactly this total.
+    pub fn probability(&self, total: i64) -> f64 {
+        if total < self.min {
+            0.0
+        } else {
+            self.probabilities
+                .get((total - self.min) as usize)
+                .copied()
+                .unwrap_or(0.0)
+        }
+    }
+
+    /// The chance of getting this total or higher.
+    pub fn at_least(&self, total: i64) -> f64 {
+        self.iter()
+            .filter(|(t, _)| *t >= total)
+            .map(|(_, p)| p)
+            .sum()
+    }
+
+    /// The chance of getting this total or lower.
+    pub fn at_most(&self, total: i64) -> f64 {
+        self.iter()
+            .filter(|(t, _)| *t <= total)
+            .map(|(_, p)| p)
+            .sum()
+    }
+
+    /// The average total.
+    pub fn mean(&self) -> f64 {
+        self.iter().map(|(t, p)| t as f64 * p).sum()
+    }
+
+    /// Iterate over every total from the lowest to the highest, with its probability.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, f64)> + '_ {
+        self.probabilities
+            .iter()
+            .enumerate()
+            .map(move |(i, p)| (self.min + i as i64, *p))
+    }
+}
+
+impl FromStr for DiceExpr {
+    type Err = DiceParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<(usize, char)> = s
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| !c.is_whitespace())
+            .map(|(pos, c)| (pos, c.to_ascii_lowercase()))
+            .collect();
+        let mut parser = Parser { chars, idx: 0 };
+
+        let mut terms = Vec::new();
+        let mut sign = match parser.peek() {
+            Some('-') => {
+                parser.idx += 1;
+                Sign::Minus
+            }
+            Some('+') => {
+                parser.idx += 1;
+                Sign::Plus
+            }
+            _ => Sign::Plus,
+        };
+        loop {
+            terms.push((sign, parser.term()?));
+            sign = match parser.next() {
+                Some((_, '+')) => Sign::Plus,
+                Some((_, '-')) => Sign::Minus,
+                Some((pos, found)) => return Err(DiceParseError::UnexpectedChar { pos, found }),
+                None => break,
+            };
+        }
+        Ok(Self { terms })
+    }
+}
+
+struct Parser {
+    /// Positions and characters, without whitespace
+    chars: Vec<(usize, char)>,
+    idx: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).map(|(_, c)| *c)
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let out = self.chars.get(self.idx).copied();
+        self.idx += 1;
+        out
+    }
+
+    /// The position of the next character, or the end of the string.
+    fn pos(&self) -> usize {
+        self.chars
+            .get(self.idx)
+            .map(|(pos, _)| *pos)
+            .unwrap_or_else(|| self.chars.last().map_or(0, |(pos, _)| pos + 1))
+    }
+
+    fn unexpected(&self) -> DiceParseError {
+        match self.chars.get(self.idx) {
+            Some(&(pos, found)) => DiceParseError::UnexpectedChar { pos, found },
+            None => DiceParseError::UnexpectedEnd,
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, DiceParseError> {
+        let pos = self.pos();
+        let mut out: Option<u32> = None;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            out = Some(
+                out.unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(digit))
+                    .ok_or(DiceParseError::NumberTooBig { pos })?,
+            );
+            self.idx += 1;
+        }
+        Ok(out)
+    }
+
+    fn term(&mut self) -> Result<Term, DiceParseError> {
+        let start = self.pos();
+        let count = self.number()?;
+        if self.peek() != Some('d') {
+            return count.map(Term::Constant).ok_or_else(|| self.unexpected());
+        }
+        self.idx += 1;
+
+        let count = count.unwrap_or(1);
+        if count == 0 {
+            return Err(DiceParseError::ZeroDice { pos: start });
+        }
+        if count > MAX_DICE {
+            return Err(DiceParseError::TooManyDice { pos: start });
+        }
+
+        let sides_pos = self.pos();
+        let sides = if self.peek() == Some('%') {
+            self.idx += 1;
+            100
+        } else {
+            self.number()?.ok_or_else(|| self.unexpected())?
+        };
+        if sides == 0 {
+            return Err(DiceParseError::ZeroSides { pos: sides_pos });
+        }
+        if sides > MAX_SIDES {
+            return Err(DiceParseError::TooManySides { pos: sides_pos });
+        }
+
+        let exploding = self.peek() == Some('!');
+        if exploding {
+            if sides == 1 {
+                return Err(DiceParseError::InfiniteExplosion { pos: self.pos() });
+            }
+            self.idx += 1;
+        }
+
+        let keep = if self.peek() == Some('k') {
+            let keep_pos = self.pos();
+            self.idx += 1;
+            let highest = match self.peek() {
+                Some('h') => true,
+                Some('l') => false,
+                _ => return Err(self.unexpected()),
+            };
+            self.idx += 1;
+            let keep = self.number()?.ok_or_else(|| self.unexpected())?;
+            if keep > count {
+                return Err(DiceParseError::KeepTooMany {
+                    pos: keep_pos,
+                    keep,
+                    count,
+                });
+            }
+            if keep_work(count, sides, exploding, keep) > MAX_KEEP_WORK {
+                return Err(DiceParseError::KeepTooSlow { pos: keep_pos });
+            }
+            Some(if highest {
+                Keep::Highest(keep)
+            } else {
+                Keep::Lowest(keep)
+            })
+        } else {
+            None
+        };
+
+        // Everything that could make this fail was checked above
+        Ok(Term::Dice(
+            DiceRoll::new(count, sides, exploding, keep).unwrap(),
+        ))
+    }
+}
+
+impl Display for DiceExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (sign, term)) in self.terms.iter().enumerate() {
+            match (idx, sign) {
+                (0, Sign::Plus) => {}
+                (0, Sign::Minus) => write!(f, "-")?,
+                (_, Sign::Plus) => write!(f, "+")?,
+                (_, Sign::Minus) => write!(f, "-")?,
+            }
+            match term {
+                Term::Constant(value) => write!(f, "{}", value)?,
+                Term::Dice(roll) => write!(f, "{}", roll)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for DiceRoll {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}d{}", self.count, self.sides)?;
+        if self.exploding {
+            write!(f, "!")?;
+        }
+        match self.keep {
+            Some(Keep::Highest(keep)) => write!(f, "kh{}", keep),
+            Some(Keep::Lowest(keep)) => write!(f, "kl{}", keep),
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn parsing() {
+    let parsed: DiceExpr = " D% - 2d20KL1 + 1d6! + 7".parse().unwrap();
+    assert_eq!(
+        parsed.terms,
+        vec![
+            (
+                Sign::Plus,
+                Term::Dice(DiceRoll::new(1, 100, false, None).unwrap())
+            ),
+            (
+                Sign::Minus,
+                Term::Dice(DiceRoll::new(2, 20, false, Some(Keep::Lowest(1))).unwrap())
+            ),
+            (
+                Sign::Plus,
+                Term::Dice(DiceRoll::new(1, 6, true, None).unwrap())
+            ),
+            (Sign::Plus, Term::Constant(7)),
+        ]
+    );
+    assert_eq!(parsed.to_string(), "1d100-2d20kl1+1d6!+7");
+    assert_eq!(parsed.to_string().parse::<DiceExpr>(), Ok(parsed));
+
+    let err = |s: &str| s.parse::<DiceExpr>().unwrap_err();
+    assert_eq!(err(""), DiceParseError::UnexpectedEnd);
+    assert_eq!(err("3d"), DiceParseError::UnexpectedEnd);
+    assert_eq!(err("3d6+"), DiceParseError::UnexpectedEnd);
+    assert_eq!(
+        err("3d6x"),
+        DiceParseError::UnexpectedChar { pos: 3, found: 'x' }
+    );
+    assert_eq!(
+        err("3d6kx"),
+        DiceParseError::UnexpectedChar { pos: 4, found: 'x' }
+    );
+    assert_eq!(err("0d6"), DiceParseError::ZeroDice { pos: 0 });
+    assert_eq!(err("2+3d0"), DiceParseError::ZeroSides { pos: 4 });
+    assert_eq!(
+        err("2d6kh3"),
+        DiceParseError::KeepTooMany {
+            pos: 3,
+            keep: 3,
+            count: 2
+        }
+    );
+    assert_eq!(err("1d1!"), DiceParseError::InfiniteExplosion { pos: 3 });
+    assert_eq!(err("99999999999"), DiceParseError::NumberTooBig { pos: 0 });
+    assert_eq!(err("4000000000d6"), DiceParseError::TooManyDice { pos: 0 });
+    assert_eq!(err("1+3d1001"), DiceParseError::TooManySides { pos: 4 });
+    assert_eq!(err("50d1000kh25"), DiceParseError::KeepTooSlow { pos: 7 });
+    assert_eq!(err("100d1000kh50"), DiceParseError::KeepTooSlow { pos: 8 });
+    assert_eq!(err("100d1000kh99"), DiceParseError::KeepTooSlow { pos: 8 });
+}
+
+#[test]
+fn distributions() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let dist = |s: &str| s.parse::<DiceExpr>().unwrap().distribution();
+
+    let advantage = dist("2d20kh1");
+    for n in 1..=20 {
+        assert!((advantage.probability(n) - (2 * n - 1) as f64 / 400.0).abs() < 1e-12);
+    }
+    let disadvantage = dist("2d20kl1");
+    assert!((disadvantage.mean() - (20.0 - advantage.mean() + 1.0)).abs() < 1e-9);
+
+    let exploding = dist("1d6!");
+    assert_eq!(exploding.probability(6), 0.0);
+    assert!((exploding.probability(8) - 1.0 / 36.0).abs() < 1e-12);
+    assert!((exploding.mean() - 4.2).abs() < 1e-6);
+
+    let shifted = dist("-1d4+10");
+    assert_eq!((shifted.min(), shifted.max()), (6, 9));
+
+    // The biggest dice allowed still work out quickly
+    let huge = dist("100d1000");
+    assert!((huge.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+    let kept = dist("100d6kh50");
+    assert_eq!((kept.min(), kept.max()), (50, 300));
+    // Including keeping dice, up to where it starts turning them down
+    let kept = dist("100d60kh50");
+    assert_eq!((kept.min(), kept.max()), (50, 3000));
+    assert!((kept.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+    assert!("100d70kh50".parse::<DiceExpr>().is_err());
+    // Keeping all of them is just rolling them
+    assert_eq!(dist("5d8kl5"), dist("5d8"));
+
+    // The rolls should agree with the distribution
+    let expr: DiceExpr = "3d6!kl2+1".parse().unwrap();
+    let odds = expr.distribution();
+    assert!((odds.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+    let mut rng = StdRng::seed_from_u64(19);
+    let rolls = 100_000;
+    let mean = (0..rolls).map(|_| expr.roll(&mut rng).total).sum::<i64>() as f64 / rolls as f64;
+    assert!(
+        (mean - odds.mean()).abs() < 0.05,
+        "{} {}",
+        mean,
+        odds.mean()
+    );
+}
@@ -0,0 +1,292 @@
+use rand::Rng;
+
+/**
+A weighted bag like [`WeightedPicker`](super::WeightedPicker), but you can add and remove items
+and change their weights whenever you like. Good for loot tables that change during play.
+
+Under the hood it's a [Fenwick tree](https://en.wikipedia.org/wiki/Fenwick_tree),
+so inserting, removing, reweighting and picking are all O(log n).
+Picking is a little slower than `WeightedPicker`'s O(1), so if your weights never change, use that.
+
+Each item gets an index when it's inserted, which stays the same until it's removed.
+Indices of removed items get reused.
+
+```
+# use cogs_gamedev::chance::DynamicWeightedPicker;
+let mut loot = DynamicWeightedPicker::new(vec![
+    ("coins", 10.0),
+    ("potion", 5.0),
+]);
+let sword = loot.insert("sword", 1.0);
+let mut rng = rand::thread_rng();
+
+// The player already has a sword, so stop dropping it
+loot.remove(sword);
+for _ in 0..100 {
+    assert_ne!(loot.get(&mut rng), Some(&"sword"));
+}
+
+// Potions are getting rarer
+loot.set_weight(1, 0.0);
+for _ in 0..100 {
+    assert_eq!(loot.get(&mut rng), Some(&"coins"));
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct DynamicWeightedPicker<T> {
+    items: Vec<Option<T>>,
+    weights: Vec<f64>,
+    /// 1-indexed; `tree[0]` is unused.
+    tree: Vec<f64>,
+    /// Indices of removed items, ready to be reused
+    free: Vec<usize>,
+    /// How many updates since the tree was last rebuilt from scratch.
+    /// Each update can add a little floating-point error, so every so often we start over.
+    updates: usize,
+}
+
+impl<T> DynamicWeightedPicker<T> {
+    /// Make a new picker with the given items and weights.
+    ///
+    /// The items get indices in the order they're given. Unlike `WeightedPicker`,
+    /// it's fine for this to be empty.
+    ///
+    /// Panics if any weight is negative or not finite.
+    pub fn new(entries: Vec<(T, f64)>) -> Self {
+        let (items, weights): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .map(|(item, weight)| {
+                check_weight(weight);
+                (Some(item), weight)
+            })
+            .unzip();
+        let mut out = Self {
+            items,
+            weights,
+            tree: Vec::new(),
+            free: Vec::new(),
+            updates: 0,
+        };
+        out.rebuild();
+        out
+    }
+
+    /// Add an item, returning its index.
+    ///
+    /// Panics if the weight is negative or not finite.
+    pub fn insert(&mut self, item: T, weight: f64) -> usize {
+        check_weight(weight);
+        match self.free.pop() {
+            Some(idx) => {
+                self.items[idx] = Some(item);
+                self.set_weight_unchecked(idx, weight);
+                idx
+            }
+            None => {
+                self.items.push(Some(item));
+                self.weights.push(weight);
+                // The new node covers the range (n - lowbit(n), n]
+                let n = self.weights.len();
+                let covered = self.prefix_sum(n - 1) - self.prefix_sum(n - lowbit(n));
+                self.tree.push(weight + covered);
+                n - 1
+            }
+        }
+    }
+
+    /// Remove the item at the given index, returning it.
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        let item = self.items.get_mut(idx)?.take()?;
+        self.set_weight_unchecked(idx, 0.0);
+        self.free.push(idx);
+        Some(item)
+    }
+
+    /// Change the weight of the item at the given index, returning the old weight.
+    ///
+    /// Returns `None` and does nothing if there's no item there.
+    ///
+    /// Panics if the weight is negative or not finite.
+    pub fn set_weight(&mut self, idx: usize, weight: f64) -> Option<f64> {
+        check_weight(weight);
+        self.items.get(idx)?.as_ref()?;
+        let old = self.weights[idx];
+        self.set_weight_unchecked(idx, weight);
+        Some(old)
+    }
+
+    /// Get the weight of the item at the given index.
+    pub fn weight(&self, idx: usize) -> Option<f64> {
+        self.items.get(idx)?.as_ref()?;
+        Some(self.weights[idx])
+    }
+
+    /// Get the sum of all the weights.
+    pub fn total_weight(&self) -> f64 {
+        self.prefix_sum(self.weights.len())
+    }
+
+    /// How many items there are.
+    pub fn len(&self) -> usize {
+        self.items.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get an item from the list.
+    ///
+    /// Returns `None` if there aren't any items with positive weight.
+    pub fn get<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        self.get_idx(rng).and_then(|idx| self.get_by_idx(idx))
+    }
+
+    /// Get an index into the internal list.
+    /// This is like [`DynamicWeightedPicker::get`], but returns the index of the
+    /// selected value instead of the value.
+    pub fn get_idx<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let total = self.total_weight();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut target = rng.gen::<f64>() * total;
+
+        // Walk down the tree to find the first item whose prefix sum is more than the target
+        let n = self.weights.len();
+        let mut pos = 0;
+        let mut step = n.next_power_of_two();
+        while step > 0 {
+            let next = pos + step;
+            if next <= n && self.tree[next] <= target {
+                target -= self.tree[next];
+                pos = next;
+            }
+            step /= 2;
+        }
+
+        if pos < n && self.weights[pos] > 0.0 {
+            Some(pos)
+        } else {
+            // Rounding error landed us on something that can't be picked.
+            // Go with the nearest thing that can.
+            (pos.min(n)..n)
+                .chain((0..pos.min(n)).rev())
+                .find(|&idx| self.weights[idx] > 0.0)
+        }
+    }
+
+    /// Manually index into the picker's array.
+    pub fn get_by_idx(&self, idx: usize) -> Option<&T> {
+        self.items.get(idx)?.as_ref()
+    }
+
+    /// Manually index into the picker's array.
+    /// You can use this to mutate entries once they've been created.
+    pub fn get_mut_by_idx(&mut self, idx: usize) -> Option<&mut T> {
+        self.items.get_mut(idx)?.as_mut()
+    }
+
+    /// Iterate over the indices, items and weights.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T, f64)> + '_ {
+        self.items
+            .iter()
+            .zip(self.weights.iter())
+            .enumerate()
+            .filter_map(|(idx, (item, weight))| item.as_ref().map(|item| (idx, item, *weight)))
+    }
+
+    fn set_weight_unchecked(&mut self, idx: usize, weight: f64) {
+        let delta = weight - self.weights[idx];
+        self.weights[idx] = weight;
+
+        self.updates += 1;
+        if self.updates > self.weights.len().max(64) {
+            self.rebuild();
+            return;
+        }
+
+        let mut i = idx + 1;
+        while i < self.tree.len() {
+            self.tree[i] += delta;
+            i += lowbit(i);
+        }
+    }
+
+    /// Sum of the first `count` weights.
+    fn prefix_sum(&self, count: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut i = count;
+        while i > 0 {
+            sum += self.tree[i];
+            i -= lowbit(i);
+        }
+        sum
+    }
+
+    fn rebuild(&mut self) {
+        self.tree.clear();
+        self.tree.push(0.0);
+        self.tree.extend(self.weights.iter().copied());
+        for i in 1..self.tree.len() {
+            let parent = i + lowbit(i);
+            if parent < self.tree.len() {
+                self.tree[parent] += self.tree[i];
+            }
+        }
+        self.updates = 0;
+    }
+}
+
+impl<T> Default for DynamicWeightedPicker<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+fn check_weight(weight: f64) {
+    assert!(
+        weight >= 0.0 && weight.is_finite(),
+        "Weights must be non-negative and finite, not {}",
+        weight
+    );
+}
+
+#[test]
+fn matches_weights() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut picker = DynamicWeightedPicker::default();
+    for i in 0..20 {
+        picker.insert(i, 1.0);
+    }
+    for i in 0..20 {
+        if i % 3 != 0 {
+            picker.remove(i);
+        }
+    }
+    picker.set_weight(0, 5.0);
+    picker.set_weight(9, 2.0);
+    // Reuses removed slots
+    assert_eq!(picker.insert(100, 2.0), 19);
+    assert_eq!(picker.len(), 8);
+    assert_eq!(picker.total_weight(), 14.0);
+
+    let mut rng = StdRng::seed_from_u64(16);
+    let mut counts = [0usize; 20];
+    let draws = 150_000;
+    for _ in 0..draws {
+        counts[picker.get_idx(&mut rng).unwrap()] += 1;
+    }
+    for (idx, _, weight) in picker.iter() {
+        let expected = weight / 14.0;
+        let actual = counts[idx] as f64 / draws as f64;
+        assert!((expected - actual).abs() < 0.01, "{} {}", expected, actual);
+    }
+    assert_eq!(counts.iter().sum::<usize>(), draws);
+}
@@ -0,0 +1,473 @@
+//! Randomness that *feels* fair, which true randomness often doesn't.
+
+use super::WeightedPicker;
+
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+/**
+A "tetris bag": draws items in a random order without repeating any until it's empty,
+then refills itself automatically.
+
+This means you never go too long without seeing any particular item, and you
+never get a long streak of the same thing.
+
+```
+# use cogs_gamedev::chance::ShuffleBag;
+let mut bag = ShuffleBag::new(vec!['I', 'O', 'T', 'S', 'Z', 'J', 'L']);
+let mut rng = rand::thread_rng();
+
+let mut first_seven: Vec<_> = (0..7).map(|_| *bag.draw(&mut rng)).collect();
+first_seven.sort();
+assert_eq!(first_seven, vec!['I', 'J', 'L', 'O', 'S', 'T', 'Z']);
+// And then it starts over
+assert_eq!(bag.remaining(), 0);
+bag.draw(&mut rng);
+assert_eq!(bag.remaining(), 6);
+```
+*/
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ShuffleBagData<T>"))]
+pub struct ShuffleBag<T> {
+    items: Vec<T>,
+    /// What a full bag has in it, as indices into `items`
+    full: Vec<usize>,
+    /// What's still in the bag, as indices into `items`
+    contents: Vec<usize>,
+}
+
+impl<T> ShuffleBag<T> {
+    /// Make a new bag with one of each item in it.
+    ///
+    /// Panics if you pass it an empty Vec.
+    pub fn new(items: Vec<T>) -> Self {
+        Self::with_counts(items.into_iter().map(|item| (item, 1)).collect())
+    }
+
+    /// Make a new bag with the given number of each item in it.
+    ///
+    /// Panics if the bag would be empty.
+    ///
+    /// ```
+    /// # use cogs_gamedev::chance::ShuffleBag;
+    /// // Every 4 attacks, exactly one is a crit
+    /// let mut crits = ShuffleBag::with_counts(vec![(false, 3), (true, 1)]);
+    /// let mut rng = rand::thread_rng();
+    /// let hits = (0..40).filter(|_| *crits.draw(&mut rng)).count();
+    /// assert_eq!(hits, 10);
+    /// ```
+    pub fn with_counts(entries: Vec<(T, usize)>) -> Self {
+        let mut items = Vec::with_capacity(entries.len());
+        let mut full = Vec::new();
+        for (idx, (item, count)) in entries.into_iter().enumerate() {
+            items.push(item);
+            full.resize(full.len() + count, idx);
+        }
+        assert_ne!(full.len(), 0, "Cannot use an empty bag!");
+
+        Self {
+            items,
+            contents: full.clone(),
+            full,
+        }
+    }
+
+    /// Take an item out of the bag, refilling it if it's empty.
+    pub fn draw<R: Rng + ?Sized>(&mut self, rng: &mut R) -> &T {
+        let idx = self.draw_idx(rng);
+        &self.items[idx]
+    }
+
+    /// Take an item out of the bag, refilling it if it's empty,
+    /// and return its index in the list of items.
+    pub fn draw_idx<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+        if self.contents.is_empty() {
+            self.refill();
+        }
+        // Picking a random one each time is just as good as shuffling up front
+        let pick = rng.gen_range(0..self.contents.len());
+        self.contents.swap_remove(pick)
+    }
+
+    /// How many items are left before the bag refills.
+    pub fn remaining(&self) -> usize {
+        self.contents.len()
+    }
+
+    /// Put everything back in the bag.
+    pub fn refill(&mut self) {
+        self.contents.clone_from(&self.full);
+    }
+
+    /// Get all the different items that can be in the bag.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+}
+
+/**
+A [pseudo-random distribution](https://dota2.fandom.com/wiki/Random_Distribution), the way Dota does it.
+
+Each roll that fails makes the next one more likely to succeed, and a success resets it.
+In the long run it succeeds with the probability you give it, but it's much less streaky:
+it's rare to get lucky several times in a row, or to go ages without getting lucky at all.
+
+```
+# use cogs_gamedev::chance::Prd;
+use rand::{rngs::StdRng, SeedableRng};
+
+let mut crit = Prd::new(0.25);
+// The first roll is much less likely than 25%...
+assert!(crit.current_chance() < 0.09);
+
+let mut rng = StdRng::seed_from_u64(0);
+let crits = (0..100_000).filter(|_| crit.roll(&mut rng)).count();
+// ...but it evens out.
+assert!((24_000..26_000).contains(&crits));
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Prd {
+    c: f64,
+    probability: f64,
+    /// How many rolls have failed in a row.
+    failures: u32,
+}
+
+impl Prd {
+    /// Make a new pseudo-random distribution that succeeds with the given probability in the long run.
+    ///
+    /// Panics if the probability isn't between 0 and 1.
+    pub fn new(probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "Probability must be between 0 and 1, not {}",
+            probability
+        );
+        // The actual probability goes up as `c` does, so binary search for it.
+        // (At 1 the search would only get *really close* to 1.)
+        let (mut low, mut high) = (0.0, probability);
+        if probability >= 1.0 {
+            low = 1.0;
+        }
+        for _ in 0..64 {
+            let mid = (low + high) / 2.0;
+            if Self::probability_for(mid) < probability {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Self {
+            c: high,
+            probability,
+            failures: 0,
+        }
+    }
+
+    /// Figure out the long-run probability of success for a given `c`.
+    fn probability_for(c: f64) -> f64 {
+        if c <= 0.0 {
+            return 0.0;
+        }
+        if c < 1e-8 {
+            // The loop below would take about 1/sqrt(c) steps, so use the asymptotic formula.
+            // This close to 0 it's accurate to about 1 part in a billion.
+            let expected_rolls = (std::f64::consts::FRAC_PI_2 / c).sqrt() - 1.0 / 3.0;
+            return 1.0 / expected_rolls;
+        }
+        let mut expected_rolls = 0.0;
+        let mut not_yet = 1.0;
+        let mut n = 1.0;
+        loop {
+            let chance = (c * n).min(1.0);
+            expected_rolls += n * not_yet * chance;
+            not_yet *= 1.0 - chance;
+            // Stop once the rest of the rolls can't make a difference
+            if chance >= 1.0 || not_yet < 1e-16 {
+                break;
+            }
+            n += 1.0;
+        }
+        1.0 / expected_rolls
+    }
+
+    /// Roll it, and see if you got lucky.
+    pub fn roll<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool {
+        if rng.gen::<f64>() < self.current_chance() {
+            self.failures = 0;
+            true
+        } else {
+            self.failures = self.failures.saturating_add(1);
+            false
+        }
+    }
+
+    /// The chance that the next roll succeeds.
+    pub fn current_chance(&self) -> f64 {
+        (self.c * (self.failures as f64 + 1.0)).min(1.0)
+    }
+
+    /// The long-run probability of success.
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// How much the chance goes up by each time a roll fails.
+    /// The first roll has this chance of succeeding.
+    pub fn c(&self) -> f64 {
+        self.c
+    }
+
+    /// Forget about any failed rolls, going back to the lowest chance.
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+/**
+Wraps a [`WeightedPicker`] with a pity timer: if you haven't gotten a rare item
+in a while, you're guaranteed to get one.
+
+```
+# use cogs_gamedev::chance::{PityPicker, WeightedPicker};
+let gacha = WeightedPicker::new(vec![
+    ("common", 90.0),
+    ("rare", 9.0),
+    ("legendary", 1.0),
+]);
+// Guarantee something good at least every 10 draws
+let mut gacha = PityPicker::new(gacha, 10, |item| *item != "common");
+let mut rng = rand::thread_rng();
+
+let mut since_good = 0;
+for _ in 0..1000 {
+    if *gacha.get(&mut rng) == "common" {
+        since_good += 1;
+    } else {
+        since_good = 0;
+    }
+    assert!(since_good < 10);
+}
+```
+*/
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "PityPickerData<T>"))]
+pub struct PityPicker<T> {
+    picker: WeightedPicker<T>,
+    /// Indices of the rare items in `picker`, with their weights
+    rare: WeightedPicker<usize>,
+    is_rare: Vec<bool>,
+    pity: usize,
+    since_rare: usize,
+}
+
+impl<T> PityPicker<T> {
+    /// Wrap a picker so that at least one of every `pity` draws is an item that `is_rare` returns `true` for.
+    ///
+    /// When the pity timer runs out, the rare item is picked using the same weights
+    /// the rare items have in the picker.
+    ///
+    /// Panics if `pity` is 0 or there aren't any rare items with positive weight.
+    pub fn new<F: FnMut(&T) -> bool>(picker: WeightedPicker<T>, pity: usize, is_rare: F) -> Self {
+        assert_ne!(pity, 0, "Pity timer must be at least 1 draw!");
+        let is_rare: Vec<bool> = picker.items.iter().map(is_rare).collect();
+        let rare_entries: Vec<_> = picker
+            .weights
+            .iter()
+            .enumerate()
+            .filter(|(idx, weight)| is_rare[*idx] && **weight > 0.0)
+            .map(|(idx, weight)| (idx, *weight))
+            .collect();
+        assert_ne!(
+            rare_entries.len(),
+            0,
+            "There must be at least one rare item!"
+        );
+
+        Self {
+            picker,
+            rare: WeightedPicker::new(rare_entries),
+            is_rare,
+            pity,
+            since_rare: 0,
+        }
+    }
+
+    /// Get an item, counting down the pity timer.
+    pub fn get<R: Rng + ?Sized>(&mut self, rng: &mut R) -> &T {
+        let idx = self.get_idx(rng);
+        &self.picker.items[idx]
+    }
+
+    /// Get an index into the internal list, counting down the pity timer.
+    /// This is like [`PityPicker::get`], but returns the index of the
+    /// selected value instead of the value.
+    pub fn get_idx<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+        let idx = if self.since_rare + 1 >= self.pity {
+            *self.rare.get(rng)
+        } else {
+            self.picker.get_idx(rng)
+        };
+        if self.is_rare[idx] {
+            self.since_rare = 0;
+        } else {
+            self.since_rare += 1;
+        }
+        idx
+    }
+
+    /// How many draws in a row haven't been rare.
+    pub fn draws_since_rare(&self) -> usize {
+        self.since_rare
+    }
+
+    /// The most draws in a row that can not be rare, plus one.
+    pub fn pity(&self) -> usize {
+        self.pity
+    }
+
+    /// Reset the pity timer, as though a rare item was just drawn.
+    pub fn reset(&mut self) {
+        self.since_rare = 0;
+    }
+
+    /// Get the picker inside.
+    pub fn picker(&self) -> &WeightedPicker<T> {
+        &self.picker
+    }
+
+    /// Turn this back into the picker inside.
+    pub fn into_picker(self) -> WeightedPicker<T> {
+        self.picker
+    }
+}
+
+/// What a [`ShuffleBag`] gets deserialized from, before checking it makes sense.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "ShuffleBag")]
+struct ShuffleBagData<T> {
+    items: Vec<T>,
+    full: Vec<usize>,
+    contents: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<ShuffleBagData<T>> for ShuffleBag<T> {
+    type Error = &'static str;
+    fn try_from(data: ShuffleBagData<T>) -> Result<Self, Self::Error> {
+        if data.full.is_empty() {
+            return Err("a shuffle bag can't be empty when it's full");
+        }
+        let len = data.items.len();
+        if data
+            .full
+            .iter()
+            .chain(data.contents.iter())
+            .any(|&idx| idx >= len)
+        {
+            return Err("a shuffle bag has something in it that isn't one of its items");
+        }
+        Ok(Self {
+            items: data.items,
+            full: data.full,
+            contents: data.contents,
+        })
+    }
+}
+
+/// What a [`PityPicker`] gets deserialized from, before checking it makes sense.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "PityPicker")]
+struct PityPickerData<T> {
+    picker: WeightedPicker<T>,
+    rare: WeightedPicker<usize>,
+    is_rare: Vec<bool>,
+    pity: usize,
+    since_rare: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<PityPickerData<T>> for PityPicker<T> {
+    type Error = &'static str;
+    fn try_from(data: PityPickerData<T>) -> Result<Self, Self::Error> {
+        if data.pity == 0 {
+            return Err("a pity timer must be at least 1 draw");
+        }
+        if data.is_rare.len() != data.picker.items.len() {
+            return Err("a pity picker needs to know whether each item is rare");
+        }
+        if data
+            .rare
+            .items
+            .iter()
+            .any(|&idx| !data.is_rare.get(idx).copied().unwrap_or(false))
+        {
+            return Err("a pity picker's rare items have to be rare items in its picker");
+        }
+        Ok(Self {
+            picker: data.picker,
+            rare: data.rare,
+            is_rare: data.is_rare,
+            pity: data.pity,
+            since_rare: data.since_rare,
+        })
+    }
+}
+
+#[test]
+fn prd_constants() {
+    // Known values from the Dota wiki
+    for &(p, c) in [(0.05, 0.003802), (0.25, 0.084744), (0.5, 0.302103)].iter() {
+        let prd = Prd::new(p);
+        assert!((prd.c() - c).abs() < 1e-5, "{} {}", p, prd.c());
+    }
+    assert_eq!(Prd::new(1.0).current_chance(), 1.0);
+    assert_eq!(Prd::new(0.0).current_chance(), 0.0);
+
+    // Tiny probabilities shouldn't take forever to work out
+    for &p in [1e-3, 1e-4, 1e-6, 1e-9].iter() {
+        let prd = Prd::new(p);
+        let c = prd.c();
+        assert!(
+            (c / (p * p) - std::f64::consts::FRAC_PI_2).abs() < 0.01,
+            "{} {}",
+            p,
+            c
+        );
+        assert!((Prd::probability_for(c) - p).abs() < p * 1e-6);
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_checks_bags() {
+    let bag = |full, contents| ShuffleBagData {
+        items: vec![1, 2],
+        full,
+        contents,
+    };
+    assert!(ShuffleBag::try_from(bag(vec![0, 1], vec![1])).is_ok());
+    assert!(ShuffleBag::try_from(bag(vec![0, 5], vec![5])).is_err());
+    assert!(ShuffleBag::try_from(bag(vec![], vec![])).is_err());
+
+    let pity = |rare| PityPickerData {
+        picker: WeightedPicker::new(vec![("common", 9.0), ("rare", 1.0)]),
+        rare: WeightedPicker::new(vec![(rare, 1.0)]),
+        is_rare: vec![false, true],
+        pity: 10,
+        since_rare: 3,
+    };
+    assert!(PityPicker::try_from(pity(1)).is_ok());
+    assert!(PityPicker::try_from(pity(0)).is_err());
+    assert!(PityPicker::try_from(pity(7)).is_err());
+}
@@ -0,0 +1,374 @@
+//! Random and probabilistic things helpful for games.
+
+use itertools::{Either, Itertools};
+use rand::Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+pub mod dice;
+mod dynamic;
+pub use dynamic::DynamicWeightedPicker;
+mod fair;
+pub use fair::{PityPicker, Prd, ShuffleBag};
+mod poisson;
+pub use poisson::{poisson_disc, poisson_disc_density, poisson_disc_grid, poisson_disc_masked};
+mod rng;
+pub use rng::GameRng;
+
+/// It's often helpful to have weighted probabilities.
+/// This struct serves as a sort of weighted bag; you can give it entries
+/// with various weights, and then randomly sample them.
+///
+/// This is the way Minecraft loot tables work, if this sounds familiar.
+///
+/// The algorithm used is [Vose's Alias Method](https://www.keithschwarz.com/darts-dice-coins/)
+/// (scroll to the bottom), which to be honest I absolutely do not understand.
+/// But it has O(n) creation and O(1) selection, so sounds good to me.
+///
+/// You can't edit the probabilities after you've created it due to the algorithm.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "WeightedPickerData<T>"))]
+pub struct WeightedPicker<T> {
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+    items: Vec<T>,
+    /// The original weights, for sampling without replacement
+    weights: Vec<f64>,
+}
+
+impl<T> WeightedPicker<T> {
+    /**
+    Initialize a WeightedPicker from the given
+    items and weights.
+
+    Panics if you pass it an empty Vec.
+
+    ```
+    # use cogs_gamedev::chance::WeightedPicker;
+
+    let picker = WeightedPicker::new(vec![
+        ("common", 10.0),
+        ("uncommon", 5.0),
+        ("rare", 2.0),
+        ("legendary", 1.0),
+        ("mythic", 0.1),
+    ]);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..10 {
+        println!("- {}", picker.get(&mut rng));
+    }
+
+    /*
+        A sample output:
+        - legendary
+        - rare
+        - uncommon
+        - common
+        - common
+        - rare
+        - uncommon
+        - common
+        - common
+        - uncommon
+    */
+    ```
+
+    */
+    pub fn new(entries: Vec<(T, f64)>) -> Self {
+        assert_ne!(entries.len(), 0, "Cannot use an empty vec!");
+
+        let total_weight: f64 = entries.iter().map(|(_, weight)| *weight).sum();
+        let len = entries.len();
+        let average = (len as f64).recip();
+
+        let (items, weights): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+
+        let (mut small, mut large): (Vec<_>, Vec<_>) = weights
+            .iter()
+            .enumerate()
+            .map(|(idx, weight)| {
+                let prob = weight / total_weight * len as f64;
+                (idx, prob)
+            })
+            .partition_map(|(idx, prob)| {
+                // true goes to small, false to large
+                if prob < average {
+                    Either::Left(idx)
+                } else {
+                    Either::Right(idx)
+                }
+            });
+
+        let mut alias = vec![0; len];
+        let mut prob = vec![0.0; len];
+
+        while !small.is_empty() && !large.is_empty() {
+            // what do you mean this is great rust code
+            let less = small.pop().unwrap();
+            let more = large.pop().unwrap();
+
+            prob[less] *= len as f64;
+            alias[less] = more;
+
+            let prev_more = prob[more];
+            let prev_less = prob[less];
+            prob[more] = prev_more + prev_less - average;
+
+            if prob[more] >= average {
+                large.push(more)
+            } else {
+                small.push(more);
+            }
+        }
+        while let Some(last) = small.pop() {
+            prob[last] = 1.0;
+        }
+        while let Some(last) = large.pop() {
+            prob[last] = 1.0;
+        }
+
+        debug_assert_eq!(prob.len(), len);
+        debug_assert_eq!(alias.len(), len);
+        debug_assert_eq!(items.len(), len);
+
+        Self {
+            alias,
+            items,
+            prob,
+            weights,
+        }
+    }
+
+    /// Get an item from the list.
+    pub fn get<R: Rng + ?Sized>(&self, rng: &mut R) -> &T {
+        &self.items[self.get_idx(rng)]
+    }
+
+    /// Get an index into the internal list.
+    /// This is like [`WeightedPicker::get`], but returns the index of the
+    /// selected value instead of the value.
+    ///
+    /// You can use this function to save some space by passing a vec
+    /// where `T` is `()`, if you want `usize` outputs, I guess.
+    pub fn get_idx<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        let column = rng.gen_range(0..self.prob.len());
+        let coin_toss = rng.gen::<f64>() < self.prob[column];
+        if coin_toss {
+            column
+        } else {
+            self.alias[column]
+        }
+    }
+
+    /// Get an endless stream of items from the list.
+    ///
+    /// ```
+    /// # use cogs_gamedev::chance::WeightedPicker;
+    /// let picker = WeightedPicker::new(vec![("heads", 1.0), ("tails", 1.0)]);
+    /// let flips: Vec<_> = picker.sample_iter(&mut rand::thread_rng()).take(10).collect();
+    /// assert_eq!(flips.len(), 10);
+    /// ```
+    pub fn sample_iter<'s, 'r, R: Rng + ?Sized>(
+        &'s self,
+        rng: &'r mut R,
+    ) -> impl Iterator<Item = &'s T> + 'r
+    where
+        's: 'r,
+    {
+        std::iter::repeat_with(move || self.get(rng))
+    }
+
+    /// Get `count` items from the list, which might repeat.
+    pub fn get_many<R: Rng + ?Sized>(&self, count: usize, rng: &mut R) -> Vec<&T> {
+        (0..count).map(|_| self.get(rng)).collect()
+    }
+
+    /**
+    Get `count` different items from the list, weighted.
+
+    This is like drawing an item, taking it out of the bag, and drawing again, `count` times.
+    Items with a weight of zero are never picked, so if there aren't enough items
+    with positive weight this returns fewer than `count` of them.
+
+    The items are in the order they were drawn.
+
+    ```
+    # use cogs_gamedev::chance::WeightedPicker;
+    let rewards = WeightedPicker::new(vec![
+        ("gold", 10.0),
+        ("gem", 3.0),
+        ("sword", 1.0),
+        ("nothing", 0.0),
+    ]);
+    let mut picked = rewards.get_distinct(3, &mut rand::thread_rng());
+    picked.sort();
+    assert_eq!(picked, vec![&"gem", &"gold", &"sword"]);
+    ```
+    */
+    pub fn get_distinct<R: Rng + ?Sized>(&self, count: usize, rng: &mut R) -> Vec<&T> {
+        self.get_distinct_idx(count, rng)
+            .into_iter()
+            .map(|idx| &self.items[idx])
+            .collect()
+    }
+
+    /// Get `count` different indices into the internal list.
+    /// This is like [`WeightedPicker::get_distinct`], but returns the indices of the
+    /// selected values instead of the values.
+    pub fn get_distinct_idx<R: Rng + ?Sized>(&self, count: usize, rng: &mut R) -> Vec<usize> {
+        distinct_indices(&self.weights, count, rng)
+    }
+
+    /// Manually index into the picker's array.
+    pub fn get_by_idx(&self, idx: usize) -> Option<&T> {
+        self.items.get(idx)
+    }
+
+    /// Manually index into the picker's array.
+    /// You can use this to mutate entries once they've been created.
+    ///
+    /// Note there is no way to mutate probabilities after creation,
+    /// nor any way to add or remove possible values.
+    pub fn get_mut_by_idx(&mut self, idx: usize) -> Option<&mut T> {
+        self.items.get_mut(idx)
+    }
+
+    /// The same as creating a WeightedPicker and then calling `get`,
+    /// but you don't need to actually make the WeightedPicker.
+    pub fn pick<R: Rng + ?Sized>(items: Vec<(T, f64)>, rng: &mut R) -> T {
+        let mut wp = WeightedPicker::new(items);
+        let idx = wp.get_idx(rng);
+        // this would be unsound to use after removal,
+        // but fortunately we don't need to use it again
+        // not sure why i can't move out of it.
+        wp.items.remove(idx)
+    }
+
+    /// The same as creating a WeightedPicker and then calling `get_distinct`,
+    /// but you don't need to actually make the WeightedPicker.
+    pub fn pick_distinct<R: Rng + ?Sized>(
+        items: Vec<(T, f64)>,
+        count: usize,
+        rng: &mut R,
+    ) -> Vec<T> {
+        let (items, weights): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+        let mut items: Vec<_> = items.into_iter().map(Some).collect();
+        distinct_indices(&weights, count, rng)
+            .into_iter()
+            .filter_map(|idx| items[idx].take())
+            .collect()
+    }
+}
+
+/// What a [`WeightedPicker`] gets deserialized from, before checking it makes sense.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "WeightedPicker")]
+struct WeightedPickerData<T> {
+    prob: Vec<f64>,
+    alias: Vec<usize>,
+    items: Vec<T>,
+    weights: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<WeightedPickerData<T>> for WeightedPicker<T> {
+    type Error = &'static str;
+    fn try_from(data: WeightedPickerData<T>) -> Result<Self, Self::Error> {
+        let len = data.items.len();
+        if len == 0 {
+            return Err("a weighted picker needs at least one item");
+        }
+        if data.prob.len() != len || data.alias.len() != len || data.weights.len() != len {
+            return Err("a weighted picker needs the same number of items, weights and aliases");
+        }
+        if data.alias.iter().any(|&alias| alias >= len)
+            || data.prob.iter().any(|prob| !(0.0..=1.0).contains(prob))
+            || data
+                .weights
+                .iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+        {
+            return Err("a weighted picker has an alias, probability or weight out of range");
+        }
+        Ok(Self {
+            prob: data.prob,
+            alias: data.alias,
+            items: data.items,
+            weights: data.weights,
+        })
+    }
+}
+
+/// Weighted sampling without replacement, using
+/// [Efraimidis and Spirakis' algorithm](https://doi.org/10.1016/j.ipl.2005.11.003):
+/// give everything a random key based on its weight, and take the ones with the biggest keys.
+fn distinct_indices<R: Rng + ?Sized>(weights: &[f64], count: usize, rng: &mut R) -> Vec<usize> {
+    let mut keys: Vec<(f64, usize)> = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0.0)
+        .map(|(idx, weight)| {
+            // This is u^(1/w), but logged so it doesn't underflow for small weights.
+            // `1 - u` so it's never ln(0)
+            let u: f64 = rng.gen();
+            ((1.0 - u).ln() / weight, idx)
+        })
+        .collect();
+    keys.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+    keys.into_iter().take(count).map(|(_, idx)| idx).collect()
+}
+
+// doctests don't println so let's replicate that test
+#[test]
+fn pick() {
+    let picker = WeightedPicker::new(vec![
+        ("common", 10.0),
+        ("uncommon", 5.0),
+        ("rare", 2.0),
+        ("legendary", 1.0),
+        ("mythic", 0.1),
+    ]);
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..10 {
+        println!("- {}", picker.get(&mut rng));
+    }
+}
+
+#[test]
+fn distinct() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let picker = WeightedPicker::new(vec![("light", 1.0), ("heavy", 9.0), ("never", 0.0)]);
+    let mut rng = StdRng::seed_from_u64(17);
+    let mut heavy_first = 0;
+    for _ in 0..10_000 {
+        let picked = picker.get_distinct(5, &mut rng);
+        assert_eq!(picked.len(), 2);
+        if picked[0] == &"heavy" {
+            heavy_first += 1;
+        }
+    }
+    // The first draw is just a normal weighted draw
+    assert!((8800..9200).contains(&heavy_first), "{}", heavy_first);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_checks_picker() {
+    let data = |alias| WeightedPickerData {
+        prob: vec![1.0, 0.5],
+        alias,
+        items: vec!["a", "b"],
+        weights: vec![2.0, 1.0],
+    };
+    assert!(WeightedPicker::try_from(data(vec![0, 0])).is_ok());
+    assert!(WeightedPicker::try_from(data(vec![0, 9])).is_err());
+    assert!(WeightedPicker::try_from(data(vec![0])).is_err());
+}
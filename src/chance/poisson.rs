@@ -0,0 +1,281 @@
+use crate::grids::{ICoord, IRect, Vec2};
+
+use rand::Rng;
+
+/// How many candidates to try around each point before giving up on it.
+/// Bridson's paper suggests 30.
+const CANDIDATES: usize = 30;
+
+/**
+Scatter points randomly inside an area, so that no two points are closer than `min_distance`
+and there aren't any big empty gaps.
+
+This is [Bridson's algorithm](https://www.cs.ubc.ca/~rbridson/docs/bridson-siggraph07-poissondisk.pdf)
+for Poisson-disc sampling. It looks a lot more natural than just picking random points,
+which clump up; it's great for scattering trees, loot, and so on.
+
+The points are anywhere in the continuous area covered by the rectangle's cells, so for `IRect::new(0, 0, 10, 10)`
+the points have coordinates from 0 up to (but not including) 10.
+
+Panics if `min_distance` isn't positive.
+
+```
+# use cogs_gamedev::chance::poisson_disc;
+# use cogs_gamedev::grids::IRect;
+let area = IRect::new(0, 0, 50, 50);
+let trees = poisson_disc(area, 4.0, &mut rand::thread_rng());
+
+for (i, a) in trees.iter().enumerate() {
+    assert!(a.x >= 0.0 && a.x < 50.0 && a.y >= 0.0 && a.y < 50.0);
+    for b in trees[i + 1..].iter() {
+        assert!(a.distance(*b) >= 4.0);
+    }
+}
+```
+*/
+pub fn poisson_disc<R: Rng + ?Sized>(
+    area: IRect,
+    min_distance: f64,
+    rng: &mut R,
+) -> Vec<Vec2<f64>> {
+    poisson_disc_masked(area, min_distance, |_| true, rng)
+}
+
+/**
+Like [`poisson_disc`], but only put points where `mask` returns `true`.
+
+```
+# use cogs_gamedev::chance::poisson_disc_masked;
+# use cogs_gamedev::grids::{IRect, Vec2};
+// Only in a circle
+let center = Vec2::new(25.0, 25.0);
+let area = IRect::new(0, 0, 50, 50);
+let points = poisson_disc_masked(area, 3.0, |p| p.distance(center) < 20.0, &mut rand::thread_rng());
+assert!(points.iter().all(|p| p.distance(center) < 20.0));
+```
+*/
+pub fn poisson_disc_masked<R: Rng + ?Sized, M: FnMut(Vec2<f64>) -> bool>(
+    area: IRect,
+    min_distance: f64,
+    mask: M,
+    rng: &mut R,
+) -> Vec<Vec2<f64>> {
+    bridson(area, min_distance, min_distance, |_| 0.0, mask, false, rng)
+}
+
+/**
+Like [`poisson_disc`], but the points are packed tighter in some places than others.
+
+`density` is sampled at each point, and should return a value from 0 to 1.
+Where it's 1, points are spaced `min_distance` apart; where it's 0, they're
+spaced `max_distance` apart, and in between is in between.
+
+Panics if `min_distance` isn't positive or `max_distance` is less than `min_distance`.
+
+```
+# use cogs_gamedev::chance::poisson_disc_density;
+# use cogs_gamedev::grids::IRect;
+// A forest that gets thicker towards the right
+let area = IRect::new(0, 0, 60, 20);
+let trees = poisson_disc_density(area, 2.0, 6.0, |p| p.x / 60.0, &mut rand::thread_rng());
+
+let left = trees.iter().filter(|p| p.x < 30.0).count();
+let right = trees.len() - left;
+assert!(right > left);
+```
+*/
+pub fn poisson_disc_density<R: Rng + ?Sized, D: FnMut(Vec2<f64>) -> f64>(
+    area: IRect,
+    min_distance: f64,
+    max_distance: f64,
+    density: D,
+    rng: &mut R,
+) -> Vec<Vec2<f64>> {
+    bridson(
+        area,
+        min_distance,
+        max_distance,
+        density,
+        |_| true,
+        false,
+        rng,
+    )
+}
+
+/**
+Like [`poisson_disc_masked`], but all the points are snapped to the cells of the grid.
+
+Every returned position is inside `area`, and they're all unique.
+
+```
+# use cogs_gamedev::chance::poisson_disc_grid;
+# use cogs_gamedev::grids::{ICoord, IRect};
+let room = IRect::new(10, 10, 12, 8);
+// Don't put any enemies next to the door
+let door = ICoord::new(10, 14);
+let enemies = poisson_disc_grid(room, 3.0, |pos| pos != door, &mut rand::thread_rng());
+
+for (i, a) in enemies.iter().enumerate() {
+    assert!(room.contains(*a));
+    assert_ne!(*a, door);
+    for b in enemies[i + 1..].iter() {
+        let delta = *a - *b;
+        assert!(((delta.x * delta.x + delta.y * delta.y) as f64).sqrt() >= 3.0);
+    }
+}
+```
+*/
+pub fn poisson_disc_grid<R: Rng + ?Sized, M: FnMut(ICoord) -> bool>(
+    area: IRect,
+    min_distance: f64,
+    mut mask: M,
+    rng: &mut R,
+) -> Vec<ICoord> {
+    bridson(
+        area,
+        min_distance,
+        min_distance,
+        |_| 0.0,
+        |p| mask(p.to_icoord_round()),
+        true,
+        rng,
+    )
+    .into_iter()
+    .map(Vec2::to_icoord_round)
+    .collect()
+}
+
+fn bridson<R, D, M>(
+    area: IRect,
+    min_distance: f64,
+    max_distance: f64,
+    mut density: D,
+    mut mask: M,
+    snap: bool,
+    rng: &mut R,
+) -> Vec<Vec2<f64>>
+where
+    R: Rng + ?Sized,
+    D: FnMut(Vec2<f64>) -> f64,
+    M: FnMut(Vec2<f64>) -> bool,
+{
+    assert!(min_distance > 0.0, "min_distance must be positive");
+    assert!(
+        max_distance >= min_distance,
+        "max_distance must be at least min_distance"
+    );
+    let mut radius = |p| {
+        let t = density(p).clamp(0.0, 1.0);
+        max_distance + (min_distance - max_distance) * t
+    };
+
+    // When snapping, the cells' centers are at integer coordinates
+    let origin = if snap { -0.5 } else { 0.0 };
+    let left = area.left as f64 + origin;
+    let top = area.top as f64 + origin;
+    let (width, height) = (area.width as f64, area.height as f64);
+    let in_area =
+        |p: Vec2<f64>| p.x >= left && p.y >= top && p.x < left + width && p.y < top + height;
+
+    // Any two points in the same bucket would be closer than `min_distance`,
+    // so each bucket has at most one point in it
+    let bucket_size = min_distance / std::f64::consts::SQRT_2;
+    let buckets_wide = (width / bucket_size).ceil() as usize + 1;
+    let buckets_high = (height / bucket_size).ceil() as usize + 1;
+    let bucket_of = |p: Vec2<f64>| {
+        (
+            ((p.x - left) / bucket_size) as usize,
+            ((p.y - top) / bucket_size) as usize,
+        )
+    };
+    let reach = (max_distance / bucket_size).ceil() as usize;
+    let mut buckets: Vec<Option<usize>> = vec![None; buckets_wide * buckets_high];
+
+    let mut points: Vec<(Vec2<f64>, f64)> = Vec::new();
+    let mut active = Vec::new();
+
+    let mut try_add = |candidate: Vec2<f64>, points: &mut Vec<(Vec2<f64>, f64)>| {
+        let candidate = if snap {
+            Vec2::new(candidate.x.round(), candidate.y.round())
+        } else {
+            candidate
+        };
+        if !in_area(candidate) || !mask(candidate) {
+            return None;
+        }
+        let r = radius(candidate);
+        let (bx, by) = bucket_of(candidate);
+        for y in by.saturating_sub(reach)..(by + reach + 1).min(buckets_high) {
+            for x in bx.saturating_sub(reach)..(bx + reach + 1).min(buckets_wide) {
+                if let Some(idx) = buckets[y * buckets_wide + x] {
+                    let (other, other_r) = points[idx];
+                    if candidate.distance(other) < r.max(other_r) {
+                        return None;
+                    }
+                }
+            }
+        }
+        buckets[by * buckets_wide + bx] = Some(points.len());
+        points.push((candidate, r));
+        Some(points.len() - 1)
+    };
+
+    loop {
+        if active.is_empty() {
+            // Find somewhere new to start. With a patchy mask there might be
+            // several areas that can't reach each other.
+            let fresh = (0..CANDIDATES).find_map(|_| {
+                let candidate = Vec2::new(
+                    left + rng.gen::<f64>() * width,
+                    top + rng.gen::<f64>() * height,
+                );
+                try_add(candidate, &mut points)
+            });
+            match fresh {
+                Some(idx) => active.push(idx),
+                None => break,
+            }
+        }
+
+        let active_idx = rng.gen_range(0..active.len());
+        let (center, r) = points[active[active_idx]];
+        let found = (0..CANDIDATES).find_map(|_| {
+            let angle = rng.gen::<f64>() * std::f64::consts::TAU;
+            let dist = r * (1.0 + rng.gen::<f64>());
+            try_add(center + Vec2::from_angle(angle) * dist, &mut points)
+        });
+        match found {
+            Some(idx) => active.push(idx),
+            None => {
+                active.swap_remove(active_idx);
+            }
+        }
+    }
+
+    points.into_iter().map(|(p, _)| p).collect()
+}
+
+#[test]
+fn fills_the_area() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let area = IRect::new(-20, 5, 40, 30);
+    let points = poisson_disc(area, 2.0, &mut StdRng::seed_from_u64(5));
+    // Every spot in the area should be near a point
+    for y in 5..35 {
+        for x in -20..20 {
+            let spot = Vec2::new(x as f64 + 0.5, y as f64 + 0.5);
+            assert!(
+                points.iter().any(|p| p.distance(spot) < 4.0),
+                "gap at {}",
+                spot
+            );
+        }
+    }
+
+    let cells = poisson_disc_grid(area, 1.0, |_| true, &mut StdRng::seed_from_u64(5));
+    let mut sorted = cells.clone();
+    sorted.sort_by_key(|c| (c.x, c.y));
+    sorted.dedup();
+    assert_eq!(sorted.len(), cells.len());
+}
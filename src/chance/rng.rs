@@ -0,0 +1,208 @@
+use crate::hash::{mix, stable_hashcode};
+
+use rand::{Error, RngCore, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::hash::Hash;
+
+/// Added to the SplitMix64 state each step.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/**
+A small, fast random number generator that gives the same numbers on every platform.
+
+It's [xoshiro256**](https://prng.di.unimi.it/) under the hood, which is plenty good for games
+(but *not* for cryptography).
+With the `serde` feature it can be serialized, so you can save it in your savefile and
+pick up exactly where you left off.
+
+You can also [`fork`](GameRng::fork) it to make separate streams of random numbers
+for different things, so that (for example) how many times combat rolls dice doesn't
+change what the world generator makes.
+
+```
+# use cogs_gamedev::chance::GameRng;
+use rand::Rng;
+
+let mut rng = GameRng::new(1234);
+let roll = rng.gen_range(1..=6);
+assert!((1..=6).contains(&roll));
+
+// The same seed always gives the same numbers
+let mut again = GameRng::new(1234);
+assert_eq!(again.gen_range(1..=6), roll);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "GameRngData"))]
+pub struct GameRng {
+    state: [u64; 4],
+}
+
+impl GameRng {
+    /// Make a new RNG from a seed.
+    pub fn new(seed: u64) -> Self {
+        let mut splitmix = seed;
+        let mut state = [0; 4];
+        for word in state.iter_mut() {
+            splitmix = splitmix.wrapping_add(GOLDEN_GAMMA);
+            *word = mix(splitmix);
+        }
+        Self::from_state(state)
+    }
+
+    /// Make a new RNG with its own stream of random numbers, based on this one and a label.
+    ///
+    /// This doesn't change this RNG, so forking it with the same label again gives the same stream.
+    /// (Unless you've used this RNG in the meantime.) Forking it with different labels gives
+    /// streams that have nothing to do with each other, or with this RNG.
+    ///
+    /// The label is hashed with [`stable_hashcode`], so it gives the same results on every platform.
+    ///
+    /// ```
+    /// # use cogs_gamedev::chance::GameRng;
+    /// use rand::Rng;
+    ///
+    /// let root = GameRng::new(99);
+    /// let mut worldgen = root.fork("worldgen");
+    /// let mut combat = root.fork("combat");
+    ///
+    /// let first_map: Vec<u32> = (0..10).map(|_| worldgen.gen()).collect();
+    ///
+    /// // Even if combat happens a different amount...
+    /// for _ in 0..50 {
+    ///     combat.gen::<u32>();
+    /// }
+    /// // ...the world gen is the same.
+    /// let mut worldgen = root.fork("worldgen");
+    /// let second_map: Vec<u32> = (0..10).map(|_| worldgen.gen()).collect();
+    /// assert_eq!(first_map, second_map);
+    /// ```
+    pub fn fork<H: Hash + ?Sized>(&self, label: &H) -> Self {
+        let label = stable_hashcode(GOLDEN_GAMMA, label);
+        let mut state = self.state;
+        for (i, word) in state.iter_mut().enumerate() {
+            let key = mix(label.wrapping_add(GOLDEN_GAMMA.wrapping_mul(i as u64 + 1)));
+            *word = mix(*word ^ key);
+        }
+        Self::from_state(state)
+    }
+
+    fn from_state(state: [u64; 4]) -> Self {
+        // xoshiro gets stuck outputting zeroes forever if all of the state is 0
+        if state == [0; 4] {
+            Self::new(0)
+        } else {
+            Self { state }
+        }
+    }
+}
+
+/// What a [`GameRng`] gets deserialized from, so it goes through `from_state`.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "GameRng")]
+struct GameRngData {
+    state: [u64; 4],
+}
+
+#[cfg(feature = "serde")]
+impl From<GameRngData> for GameRng {
+    fn from(data: GameRngData) -> Self {
+        Self::from_state(data.state)
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        // The high bits are the best ones
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let out = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        out
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for GameRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            let mut le = [0; 8];
+            le.copy_from_slice(bytes);
+            *word = u64::from_le_bytes(le);
+        }
+        Self::from_state(state)
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+#[test]
+fn golden_values() {
+    // These must never change between versions, or everyone's savefiles break!
+    let mut rng = GameRng::new(0);
+    let outputs: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+    assert_eq!(
+        outputs,
+        vec![
+            11091344671253066420,
+            13793997310169335082,
+            1900383378846508768,
+            7684712102626143532
+        ]
+    );
+
+    let mut forked = GameRng::new(0).fork("loot");
+    assert_eq!(forked.next_u64(), 7040838190516260159);
+
+    // Known values for xoshiro256** from its reference implementation
+    let mut reference = GameRng::from_state([1, 2, 3, 4]);
+    let outputs: Vec<u64> = (0..4).map(|_| reference.next_u64()).collect();
+    assert_eq!(outputs, vec![11520, 0, 1509978240, 1215971899390074240]);
+
+    assert_eq!(GameRng::from_seed([0; 32]), GameRng::new(0));
+    assert_ne!(GameRng::new(0).fork("a"), GameRng::new(0).fork("b"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_avoids_zero_state() {
+    let mut rng = GameRng::from(GameRngData { state: [0; 4] });
+    assert_ne!(rng.next_u64(), 0);
+    assert_eq!(
+        GameRng::from(GameRngData {
+            state: [1, 2, 3, 4]
+        }),
+        GameRng::from_state([1, 2, 3, 4])
+    );
+}
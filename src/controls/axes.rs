@@ -0,0 +1,419 @@
+use std::hash::Hash;
+
+use ahash::AHashMap;
+use enum_map::Enum;
+
+use crate::{ease::Interpolator, grids::Vec2};
+
+use super::bindings::same_control;
+
+/// Something that moves an axis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AxisBinding<I, C> {
+    /// An analog input, like a trigger or one direction of a stick.
+    /// Its value is whatever you last gave the handler with `set_analog`.
+    Analog(I),
+    /// A "virtual axis" made from two digital controls.
+    /// It's -1 when only `negative` is pressed, 1 when only `positive` is, and 0 otherwise.
+    Virtual { negative: C, positive: C },
+}
+
+/// How to ignore small movements of an analog input, which are usually just the stick
+/// not quite sitting in the middle.
+///
+/// Past the deadzone, the value is scaled so it still goes smoothly from 0 to 1.
+/// Sizes are clamped between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deadzone {
+    /// Ignore the axis if it's too close to 0, regardless of any other axis.
+    ///
+    /// This makes it easy to move in exactly straight lines, but diagonals feel "sticky".
+    Axial(f32),
+    /// When two axes are combined into a 2D vector, ignore the vector if it's too short.
+    ///
+    /// This feels much smoother for sticks. For 1D axes it's the same as `Axial`.
+    /// It's only used if both axes in the vector use it, and then the bigger of the two sizes
+    /// is used. The response curve gets applied to the length of the whole vector,
+    /// so only the x axis's curve is used.
+    Radial(f32),
+}
+
+impl Deadzone {
+    fn size(self) -> f32 {
+        match self {
+            Deadzone::Axial(size) | Deadzone::Radial(size) if size.is_nan() => 0.0,
+            Deadzone::Axial(size) | Deadzone::Radial(size) => size.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// How an axis responds to how far it's being pushed, using the [easing functions](crate::ease).
+///
+/// These are applied to how far the axis is pushed, after the deadzone.
+/// Curves that start slow (like `QuadIn`) make it easier to make small, precise movements.
+#[derive(Debug, Clone, Copy)]
+pub enum ResponseCurve {
+    Linear,
+    SineIn,
+    SineOut,
+    SineInOut,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    /// Any other function from 0..1 to 0..1.
+    Custom(fn(f32) -> f32),
+}
+
+impl ResponseCurve {
+    /// Apply the curve to a value from 0 to 1.
+    pub fn apply(self, amount: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => amount,
+            ResponseCurve::SineIn => amount.sine_in(0.0, 1.0),
+            ResponseCurve::SineOut => amount.sine_out(0.0, 1.0),
+            ResponseCurve::SineInOut => amount.sine_in_out(0.0, 1.0),
+            ResponseCurve::QuadIn => amount.quad_in(0.0, 1.0),
+            ResponseCurve::QuadOut => amount.quad_out(0.0, 1.0),
+            ResponseCurve::QuadInOut => amount.quad_in_out(0.0, 1.0),
+            ResponseCurve::Custom(curve) => curve(amount),
+        }
+    }
+}
+
+/// How to turn the raw value of an axis into the value the game sees.
+#[derive(Debug, Clone, Copy)]
+pub struct AxisSettings {
+    pub deadzone: Deadzone,
+    pub curve: ResponseCurve,
+    /// Flip the axis around, so -1 is 1 and 1 is -1.
+    pub invert: bool,
+}
+
+/// A radial deadzone of 0.1 and a linear response.
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: Deadzone::Radial(0.1),
+            curve: ResponseCurve::Linear,
+            invert: false,
+        }
+    }
+}
+
+/**
+Which analog inputs and virtual axes move which controls, and how.
+
+Axes are identified by controls, just like buttons are. Query them with
+[`InputHandler::axis`](super::InputHandler::axis) and [`InputHandler::axis2`](super::InputHandler::axis2).
+
+If an axis has several bindings, whichever is pushed furthest wins.
+So you can bind a stick and the arrow keys to the same axes, and your movement code doesn't care which the player uses.
+
+```
+# use cogs_gamedev::controls::{EventInputHandler, InputHandler};
+# use enum_map::Enum;
+#[derive(Enum, Clone, Copy, Debug)]
+enum Control {
+    Left,
+    Right,
+    Up,
+    Down,
+    MoveX,
+    MoveY,
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+enum Input {
+    Key(char),
+    StickX,
+    StickY,
+}
+
+let mut handler = EventInputHandler::new_empty();
+let bindings = handler.bindings_mut();
+bindings.bind(Input::Key('a'), Control::Left);
+bindings.bind(Input::Key('d'), Control::Right);
+bindings.bind(Input::Key('w'), Control::Up);
+bindings.bind(Input::Key('s'), Control::Down);
+
+let axes = handler.axes_mut();
+axes.bind_virtual(Control::Left, Control::Right, Control::MoveX);
+axes.bind_virtual(Control::Up, Control::Down, Control::MoveY);
+axes.bind_analog(Input::StickX, Control::MoveX);
+axes.bind_analog(Input::StickY, Control::MoveY);
+
+// Moving diagonally with the keyboard isn't any faster than moving straight
+handler.input_down(Input::Key('d'));
+handler.input_down(Input::Key('s'));
+handler.update();
+let movement = handler.axis2(Control::MoveX, Control::MoveY);
+assert!((movement.length() - 1.0).abs() < 1e-6);
+assert!(movement.x > 0.0 && movement.y > 0.0);
+
+// Stick drift is ignored
+handler.input_up(Input::Key('d'));
+handler.input_up(Input::Key('s'));
+handler.set_analog(Input::StickX, 0.05);
+handler.update();
+assert_eq!(handler.axis(Control::MoveX), 0.0);
+
+handler.set_analog(Input::StickX, -1.0);
+handler.update();
+assert_eq!(handler.axis(Control::MoveX), -1.0);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct Axes<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> {
+    bindings: Vec<(AxisBinding<I, C>, C)>,
+    settings: Vec<(C, AxisSettings)>,
+}
+
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Axes<I, C> {
+    /// Make a new set of axes without any bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            settings: Vec::new(),
+        }
+    }
+
+    /// Make the binding move the axis, on top of anything else already bound to it.
+    pub fn bind(&mut self, binding: AxisBinding<I, C>, axis: C) {
+        self.bindings.push((binding, axis));
+    }
+
+    /// Make an analog input move the axis.
+    pub fn bind_analog(&mut self, input: I, axis: C) {
+        self.bind(AxisBinding::Analog(input), axis);
+    }
+
+    /// Make two digital controls move the axis.
+    pub fn bind_virtual(&mut self, negative: C, positive: C, axis: C) {
+        self.bind(AxisBinding::Virtual { negative, positive }, axis);
+    }
+
+    /// Remove every binding for the axis, returning them.
+    pub fn clear_axis(&mut self, axis: C) -> Vec<AxisBinding<I, C>> {
+        let mut removed = Vec::new();
+        self.bindings.retain(|(b, c)| {
+            if same_control(c, &axis) {
+                removed.push(b.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Iterate over all the bindings for the axis.
+    pub fn bindings_for(&self, axis: C) -> impl Iterator<Item = &AxisBinding<I, C>> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, c)| same_control(c, &axis))
+            .map(|(b, _)| b)
+    }
+
+    /// Get the settings for the axis.
+    pub fn settings(&self, axis: C) -> AxisSettings {
+        self.settings
+            .iter()
+            .find(|(c, _)| same_control(c, &axis))
+            .map(|(_, settings)| *settings)
+            .unwrap_or_default()
+    }
+
+    /// Change the settings for the axis.
+    pub fn set_settings(&mut self, axis: C, settings: AxisSettings) {
+        match self
+            .settings
+            .iter_mut()
+            .find(|(c, _)| same_control(c, &axis))
+        {
+            Some((_, old)) => *old = settings,
+            None => self.settings.push((axis, settings)),
+        }
+    }
+
+    /// The value of the axis before the deadzone and curve, from -1 to 1.
+    fn raw<P: Fn(C) -> bool>(&self, axis: C, analog: &AHashMap<I, f32>, pressed: &P) -> f32 {
+        let raw = self
+            .bindings_for(axis.clone())
+            .map(|binding| match binding {
+                // Infinite or NaN values from a misbehaving device count as the axis at rest
+                AxisBinding::Analog(input) => analog
+                    .get(input)
+                    .copied()
+                    .filter(|value| value.is_finite())
+                    .unwrap_or(0.0),
+                AxisBinding::Virtual { negative, positive } => {
+                    let to_f32 = |pressed: bool| if pressed { 1.0 } else { 0.0 };
+                    to_f32(pressed(positive.clone())) - to_f32(pressed(negative.clone()))
+                }
+            })
+            .fold(0.0f32, |best, value| {
+                if value.abs() > best.abs() {
+                    value
+                } else {
+                    best
+                }
+            })
+            .clamp(-1.0, 1.0);
+        if self.settings(axis).invert {
+            -raw
+        } else {
+            raw
+        }
+    }
+
+    /// The value of the axis the game sees.
+    pub(crate) fn value<P: Fn(C) -> bool>(
+        &self,
+        axis: C,
+        analog: &AHashMap<I, f32>,
+        pressed: P,
+    ) -> f32 {
+        let settings = self.settings(axis.clone());
+        let raw = self.raw(axis, analog, &pressed);
+        let amount = settings
+            .curve
+            .apply(past_deadzone(raw.abs(), settings.deadzone));
+        amount.copysign(raw)
+    }
+
+    /// The value of two axes combined into a vector the game sees.
+    pub(crate) fn value2<P: Fn(C) -> bool>(
+        &self,
+        x: C,
+        y: C,
+        analog: &AHashMap<I, f32>,
+        pressed: P,
+    ) -> Vec2<f32> {
+        let (x_settings, y_settings) = (self.settings(x.clone()), self.settings(y.clone()));
+        if let (Deadzone::Radial(x_deadzone), Deadzone::Radial(y_deadzone)) =
+            (x_settings.deadzone, y_settings.deadzone)
+        {
+            let raw = Vec2::new(self.raw(x, analog, &pressed), self.raw(y, analog, &pressed));
+            let length = raw.length();
+            if length == 0.0 {
+                return raw;
+            }
+            let deadzone = Deadzone::Radial(x_deadzone.max(y_deadzone));
+            let amount = x_settings
+                .curve
+                .apply(past_deadzone(length.min(1.0), deadzone));
+            raw * (amount / length)
+        } else {
+            let out = Vec2::new(
+                self.value(x, analog, &pressed),
+                self.value(y, analog, &pressed),
+            );
+            if out.length_squared() > 1.0 {
+                out.normalize()
+            } else {
+                out
+            }
+        }
+    }
+}
+
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Default for Axes<I, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Given how far an axis is pushed from 0 to 1, scale it so the deadzone is 0.
+fn past_deadzone(amount: f32, deadzone: Deadzone) -> f32 {
+    let size = deadzone.size();
+    if amount <= size {
+        0.0
+    } else {
+        ((amount - size) / (1.0 - size)).min(1.0)
+    }
+}
+
+#[test]
+fn deadzones_and_curves() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Left,
+        Right,
+        X,
+        Y,
+    }
+
+    let mut axes = Axes::new();
+    axes.bind_analog("x", Control::X);
+    axes.bind_analog("y", Control::Y);
+    axes.bind_virtual(Control::Left, Control::Right, Control::X);
+
+    let analog =
+        |x: f32, y: f32| -> AHashMap<&str, f32> { [("x", x), ("y", y)].iter().copied().collect() };
+    let nothing = |_| false;
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+
+    // Radial: a diagonal push bigger than the deadzone gets through,
+    // even though neither axis does on its own
+    let small = analog(0.08, 0.08);
+    assert_eq!(axes.value(Control::X, &small, nothing), 0.0);
+    assert!(
+        axes.value2(Control::X, Control::Y, &small, nothing)
+            .length()
+            > 0.0
+    );
+    // And past the deadzone it's rescaled
+    assert!(close(
+        axes.value(Control::X, &analog(-0.55, 0.0), nothing),
+        -0.5
+    ));
+
+    // Axial: it doesn't
+    for axis in [Control::X, Control::Y].iter() {
+        axes.set_settings(
+            *axis,
+            AxisSettings {
+                deadzone: Deadzone::Axial(0.1),
+                curve: ResponseCurve::QuadIn,
+                invert: false,
+            },
+        );
+    }
+    assert_eq!(
+        axes.value2(Control::X, Control::Y, &small, nothing),
+        Vec2::zero()
+    );
+    assert!(close(
+        axes.value(Control::X, &analog(0.55, 0.0), nothing),
+        0.25
+    ));
+
+    // The keyboard wins when it's pushed further
+    let right = |c| matches!(c, Control::Right);
+    assert_eq!(axes.value(Control::X, &analog(-0.5, 0.0), right), 1.0);
+    let both = |c| matches!(c, Control::Right | Control::Left);
+    assert!(close(
+        axes.value(Control::X, &analog(-0.55, 0.0), both),
+        -0.25
+    ));
+
+    // Bad values don't push the axis
+    assert_eq!(axes.value(Control::X, &analog(f32::NAN, 0.0), nothing), 0.0);
+    assert_eq!(
+        axes.value2(Control::X, Control::Y, &analog(0.0, f32::INFINITY), nothing),
+        Vec2::zero()
+    );
+    axes.set_settings(
+        Control::X,
+        AxisSettings {
+            deadzone: Deadzone::Axial(-0.5),
+            curve: ResponseCurve::Linear,
+            invert: false,
+        },
+    );
+    assert_eq!(axes.value(Control::X, &analog(0.0, 0.0), nothing), 0.0);
+    assert!(close(
+        axes.value(Control::X, &analog(0.5, 0.0), nothing),
+        0.5
+    ));
+}
@@ -0,0 +1,291 @@
+use std::hash::Hash;
+
+use ahash::AHashMap;
+use enum_map::{Enum, EnumMap};
+
+/// One way to activate a control: either a single input, or a chord of several
+/// inputs that all have to be held at once (like Ctrl+S).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Binding<I> {
+    inputs: Vec<I>,
+}
+
+impl<I: Hash + Eq + PartialEq + Clone> Binding<I> {
+    /// A binding for just one input.
+    pub fn new(input: I) -> Self {
+        Self {
+            inputs: vec![input],
+        }
+    }
+
+    /// A binding that's only active when all of these inputs are held.
+    ///
+    /// Panics if `inputs` is empty.
+    pub fn chord(inputs: Vec<I>) -> Self {
+        assert_ne!(inputs.len(), 0, "A chord needs at least one input!");
+        let mut deduped: Vec<I> = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            if !deduped.contains(&input) {
+                deduped.push(input);
+            }
+        }
+        Self { inputs: deduped }
+    }
+
+    /// The inputs that have to be held for this binding to be active.
+    pub fn inputs(&self) -> &[I] {
+        &self.inputs
+    }
+
+    /// Does this binding need more than one input?
+    pub fn is_chord(&self) -> bool {
+        self.inputs.len() > 1
+    }
+
+    /// Does this binding need this input?
+    pub fn contains(&self, input: &I) -> bool {
+        self.inputs.contains(input)
+    }
+
+    /// Does every input in this binding also appear in `other`, which needs more inputs?
+    fn is_part_of(&self, other: &Binding<I>) -> bool {
+        other.inputs.len() > self.inputs.len() && self.inputs.iter().all(|i| other.contains(i))
+    }
+}
+
+impl<I: Hash + Eq + PartialEq + Clone> From<I> for Binding<I> {
+    fn from(input: I) -> Self {
+        Self::new(input)
+    }
+}
+
+/**
+Which inputs activate which controls.
+
+This is many-to-many: a control can have several bindings (say, a keyboard key and
+a gamepad button), and an input can be part of bindings for several controls.
+
+If a chord is active, any smaller bindings made from some of the same inputs don't count.
+So if Ctrl+S is bound to `Save` and S is bound to `Down`, pressing Ctrl+S only saves.
+
+```
+# use cogs_gamedev::controls::{Binding, Bindings};
+# use enum_map::Enum;
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Control {
+    Jump,
+    Save,
+}
+
+let mut bindings = Bindings::new();
+bindings.bind("Space", Control::Jump);
+bindings.bind("GamepadA", Control::Jump);
+bindings.bind(Binding::chord(vec!["Ctrl", "S"]), Control::Save);
+
+assert_eq!(bindings.bindings_for(Control::Jump).count(), 2);
+assert_eq!(bindings.controls_for(&"S").collect::<Vec<_>>(), vec![&Control::Save]);
+
+// Moving jump to W
+let old = bindings.replace(Control::Jump, vec![Binding::new("W")]);
+assert_eq!(old, vec![Binding::new("Space"), Binding::new("GamepadA")]);
+assert_eq!(bindings.controls_for(&"Space").count(), 0);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct Bindings<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> {
+    bindings: Vec<(Binding<I>, C)>,
+}
+
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Bindings<I, C> {
+    /// Make an empty set of bindings.
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Make the binding activate the control, on top of anything else already bound to it.
+    ///
+    /// Does nothing if it was already bound to that control.
+    pub fn bind(&mut self, binding: impl Into<Binding<I>>, control: C) {
+        let binding = binding.into();
+        if !self
+            .bindings
+            .iter()
+            .any(|(b, c)| *b == binding && same_control(c, &control))
+        {
+            self.bindings.push((binding, control));
+        }
+    }
+
+    /// Stop the binding from activating the control.
+    ///
+    /// Returns whether it was bound in the first place.
+    pub fn unbind(&mut self, binding: &Binding<I>, control: C) -> bool {
+        let before = self.bindings.len();
+        self.bindings
+            .retain(|(b, c)| !(b == binding && same_control(c, &control)));
+        self.bindings.len() != before
+    }
+
+    /// Remove every binding for the control, returning them.
+    pub fn clear_control(&mut self, control: C) -> Vec<Binding<I>> {
+        let mut removed = Vec::new();
+        self.bindings.retain(|(b, c)| {
+            if same_control(c, &control) {
+                removed.push(b.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Replace all the bindings for the control with new ones, returning the old ones.
+    pub fn replace(&mut self, control: C, bindings: Vec<Binding<I>>) -> Vec<Binding<I>> {
+        let old = self.clear_control(control.clone());
+        for binding in bindings {
+            self.bind(binding, control.clone());
+        }
+        old
+    }
+
+    /// Iterate over all the bindings for the control.
+    pub fn bindings_for(&self, control: C) -> impl Iterator<Item = &Binding<I>> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, c)| same_control(c, &control))
+            .map(|(b, _)| b)
+    }
+
+    /// Iterate over all the controls that have a binding using this input,
+    /// either on its own or as part of a chord.
+    ///
+    /// Controls with several bindings using the input show up several times.
+    pub fn controls_for<'a>(&'a self, input: &'a I) -> impl Iterator<Item = &'a C> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |(b, _)| b.contains(input))
+            .map(|(_, c)| c)
+    }
+
+    /// Iterate over every binding and the control it activates.
+    pub fn iter(&self) -> impl Iterator<Item = (&Binding<I>, &C)> + '_ {
+        self.bindings.iter().map(|(b, c)| (b, c))
+    }
+
+    /// Remove all bindings.
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    /// Given which inputs are held, count how many bindings are active for each control.
+    pub(crate) fn active<F: Fn(&I) -> bool>(&self, held: F) -> EnumMap<C, u32> {
+        let active: Vec<&(Binding<I>, C)> = self
+            .bindings
+            .iter()
+            .filter(|(b, _)| b.inputs.iter().all(&held))
+            .collect();
+
+        let mut out = EnumMap::default();
+        for (binding, control) in active.iter() {
+            let in_bigger_chord = active.iter().any(|(other, _)| binding.is_part_of(other));
+            if !in_bigger_chord {
+                out[control.clone()] += 1;
+            }
+        }
+        out
+    }
+}
+
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Default for Bindings<I, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Make bindings from a map of single inputs to the controls they activate.
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> From<AHashMap<I, C>>
+    for Bindings<I, C>
+{
+    fn from(map: AHashMap<I, C>) -> Self {
+        Self {
+            bindings: map
+                .into_iter()
+                .map(|(input, control)| (Binding::new(input), control))
+                .collect(),
+        }
+    }
+}
+
+/// A control waiting to be rebound.
+#[derive(Clone)]
+pub(crate) struct Listening<I, C> {
+    control: C,
+    /// The binding to replace, or `None` to replace all of them
+    replacing: Option<Binding<I>>,
+}
+
+impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Listening<I, C> {
+    pub(crate) fn new(control: C, replacing: Option<Binding<I>>) -> Self {
+        Self { control, replacing }
+    }
+
+    pub(crate) fn rebind(self, bindings: &mut Bindings<I, C>, input: I) {
+        match self.replacing {
+            None => {
+                bindings.replace(self.control, vec![Binding::new(input)]);
+            }
+            Some(old) => {
+                bindings.unbind(&old, self.control.clone());
+                bindings.bind(input, self.control);
+            }
+        }
+    }
+}
+
+pub(crate) fn same_control<C: Enum<u32> + Clone>(a: &C, b: &C) -> bool {
+    <C as Enum<u32>>::into_usize(a.clone()) == <C as Enum<u32>>::into_usize(b.clone())
+}
+
+#[test]
+fn chords_and_multiple_bindings() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Down,
+        Save,
+        Confirm,
+    }
+
+    let mut bindings = Bindings::new();
+    bindings.bind("s", Control::Down);
+    bindings.bind("down", Control::Down);
+    bindings.bind(Binding::chord(vec!["ctrl", "s"]), Control::Save);
+    bindings.bind("enter", Control::Confirm);
+    // One input, two controls
+    bindings.bind("enter", Control::Save);
+
+    let active = |held: &[&str]| bindings.active(|i| held.contains(i));
+
+    let pressed = active(&["s"]);
+    assert_eq!(pressed[Control::Down], 1);
+    assert_eq!(pressed[Control::Save], 0);
+
+    // The chord hides the plain S
+    let pressed = active(&["s", "ctrl"]);
+    assert_eq!(pressed[Control::Down], 0);
+    assert_eq!(pressed[Control::Save], 1);
+    // But not other bindings for the same control
+    let pressed = active(&["s", "ctrl", "down"]);
+    assert_eq!(pressed[Control::Down], 1);
+
+    let pressed = active(&["enter", "down"]);
+    assert_eq!(pressed[Control::Down], 1);
+    assert_eq!(pressed[Control::Save], 1);
+    assert_eq!(pressed[Control::Confirm], 1);
+
+    assert!(bindings.unbind(&Binding::new("enter"), Control::Save));
+    assert!(!bindings.unbind(&Binding::new("enter"), Control::Save));
+    assert_eq!(bindings.controls_for(&"enter").count(), 1);
+}
@@ -1,54 +1,72 @@
 use std::hash::Hash;
 
-use ahash::AHashMap;
-use enum_map::{Enum, EnumMap};
+use ahash::{AHashMap, AHashSet};
+use enum_map::Enum;
 
-use super::InputHandler;
+use super::{
+    bindings::Listening, state::ControlState, Axes, Binding, Bindings, InputHandler, InputHistory,
+    RepeatProfile,
+};
+use crate::grids::Vec2;
 
 /// Event-based input handler
 /// See module-level documentation for more detail.
+#[derive(Clone)]
 pub struct EventInputHandler<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> {
     /// Maps inputs to the controls they activate
-    control_config: AHashMap<I, C>,
-    /// How long each control has been pressed
-    input_time: EnumMap<C, u32>,
+    control_config: Bindings<I, C>,
+    /// How long each control has been pressed, and so on
+    state: ControlState<C>,
     /// If this is Some, we're waiting for a new control config.
-    listening_for_input: Option<C>,
-    /// The set of all the control events we've gotten since we last called `update`
-    pressed_controls: EnumMap<C, bool>,
+    listening_for_input: Option<Listening<I, C>>,
+    /// The set of all the inputs that are held down, according to the events we've gotten
+    held_inputs: AHashSet<I>,
+    /// Maps analog inputs and virtual axes to the controls they move
+    axes: Axes<I, C>,
+    /// The value of each analog input as of the last `update`
+    analog_values: AHashMap<I, f32>,
+    /// The value of each analog input, as we've been told since the last `update`
+    new_analog_values: AHashMap<I, f32>,
+    /// The axes to record directions in the history from
+    direction_axes: Option<(C, C)>,
 }
 
 impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> EventInputHandler<I, C> {
     /// Create a new EventInputHandler without any controls.
     pub fn new_empty() -> Self {
-        Self::new(AHashMap::new())
+        Self::with_bindings(Bindings::new())
     }
 
     /// Create a new EventInputHandler with the specified controls.
     /// The HashMap in should map inputs to the controls you want them to actuate.
     pub fn new(control_config: AHashMap<I, C>) -> Self {
+        Self::with_bindings(control_config.into())
+    }
+
+    /// Create a new EventInputHandler with the specified bindings.
+    pub fn with_bindings(control_config: Bindings<I, C>) -> Self {
         Self {
             control_config,
-            input_time: EnumMap::default(),
+            state: ControlState::new(),
             listening_for_input: None,
-            pressed_controls: EnumMap::default(),
+            axes: Axes::new(),
+            analog_values: AHashMap::new(),
+            new_analog_values: AHashMap::new(),
+            direction_axes: None,
+            held_inputs: AHashSet::new(),
         }
     }
 
     /// Call this function when your game engine gives you a KeyDown event,
     /// or any event signaling that an input is newly pressed down.
     pub fn input_down(&mut self, input: I) {
-        match &self.listening_for_input {
+        match self.listening_for_input.take() {
             None => {
-                if let Some(control) = self.control_config.get(&input) {
-                    self.pressed_controls[control.to_owned()] = true;
-                }
+                self.held_inputs.insert(input);
             }
-            Some(ctrl) => {
-                // Update the control ...
-                self.control_config.insert(input, ctrl.to_owned());
-                // and stop listening for inputs
-                self.listening_for_input = None;
+            Some(listening) => {
+                // Update the control and stop listening for inputs
+                listening.rebind(&mut self.control_config, input);
             }
         }
     }
@@ -56,9 +74,7 @@ impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> EventI
     /// Call this function when your game engine gives you a KeyUp event,
     /// or any event signaling that an input has been released.
     pub fn input_up(&mut self, input: I) {
-        if let Some(control) = self.control_config.get(&input) {
-            self.pressed_controls[control.to_owned()] = false;
-        }
+        self.held_inputs.remove(&input);
     }
 
     /// Manually clear all the inputs the handler has received.
@@ -66,26 +82,95 @@ impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> EventI
     /// (I'm not sure why you would want to do this, but hey, might as well
     /// expose the functionality.)
     pub fn clear_inputs(&mut self) {
-        self.pressed_controls.clear();
+        self.held_inputs.clear();
     }
 
     /// Update the input handler. You MUST CALL THIS FIRST THING in your game loop.
     /// Otherwise things won't get updated correctly.
     pub fn update(&mut self) {
+        self.analog_values.clone_from(&self.new_analog_values);
+
         if self.listening_for_input.is_none() {
-            for (control, pressed) in self.pressed_controls.iter() {
-                if *pressed {
-                    // this input is getting pressed!
-                    // increment our timer
-                    self.input_time[control] += 1;
-                } else {
-                    // this input is not getting pressed
-                    // reset our timer
-                    self.input_time[control] = 0;
-                }
+            let held_inputs = &self.held_inputs;
+            let active = self
+                .control_config
+                .active(|input| held_inputs.contains(input));
+            self.state.update(&active);
+            if let Some((x, y)) = &self.direction_axes {
+                let state = &self.state;
+                let direction = self
+                    .axes
+                    .value2(x.clone(), y.clone(), &self.analog_values, |c| {
+                        state.held_frames(c) > 0
+                    });
+                self.state.history_mut().record_direction(direction);
             }
         }
     }
+
+    /// The next time an input is pressed, make it the only binding for this control.
+    pub fn listen_for_control_change(&mut self, control: C) {
+        self.listening_for_input = Some(Listening::new(control, None));
+    }
+
+    /// The next time an input is pressed, bind it to this control in place of `old`.
+    /// The control's other bindings are left alone.
+    pub fn listen_for_binding_change(&mut self, control: C, old: Binding<I>) {
+        self.listening_for_input = Some(Listening::new(control, Some(old)));
+    }
+
+    /// Is the handler waiting for an input to change a control to?
+    pub fn is_listening(&self) -> bool {
+        self.listening_for_input.is_some()
+    }
+
+    /// Stop waiting for an input to change a control to.
+    pub fn stop_listening(&mut self) {
+        self.listening_for_input = None;
+    }
+
+    /// Get the bindings from inputs to controls.
+    pub fn bindings(&self) -> &Bindings<I, C> {
+        &self.control_config
+    }
+
+    /// Get the bindings from inputs to controls, so you can change them.
+    pub fn bindings_mut(&mut self) -> &mut Bindings<I, C> {
+        &mut self.control_config
+    }
+
+    /// Tell the handler the value of an analog input, like how far a stick or trigger is pushed.
+    /// It stays at that value until you give it another one.
+    ///
+    /// This takes effect the next time you call `update`.
+    pub fn set_analog(&mut self, input: I, value: f32) {
+        self.new_analog_values.insert(input, value);
+    }
+
+    /// Get the bindings for analog inputs and virtual axes.
+    pub fn axes(&self) -> &Axes<I, C> {
+        &self.axes
+    }
+
+    /// Get the bindings for analog inputs and virtual axes, so you can change them.
+    pub fn axes_mut(&mut self) -> &mut Axes<I, C> {
+        &mut self.axes
+    }
+
+    /// Change how this control repeats when you call `repeats`.
+    pub fn set_repeat_profile(&mut self, control: C, profile: RepeatProfile) {
+        self.state.set_repeat_profile(control, profile);
+    }
+
+    /// Record which way these axes point in the history, so they can be used in `Sequence`s.
+    pub fn set_direction_axes(&mut self, x: C, y: C) {
+        self.direction_axes = Some((x, y));
+    }
+
+    /// Change how many events the history remembers.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.state.history_mut().set_capacity(capacity);
+    }
 }
 
 // there's gotta be a better way to do these generics
@@ -95,45 +180,96 @@ impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> InputH
     /// Is this input pressed down?
     /// i.e. is the player pressing the button?
     fn pressed(&self, control: C) -> bool {
-        self.input_time[control] >= 1
+        self.state.held_frames(control) >= 1
     }
 
     /// Is this input released?
     /// i.e. is the player *not* pressing the button?
     fn released(&self, control: C) -> bool {
-        self.input_time[control] == 0
+        self.state.held_frames(control) == 0
     }
 
     /// Is this input being clicked down?
     /// i.e. was it up last frame, but down this frame?
     fn clicked_down(&self, control: C) -> bool {
-        self.input_time[control] == 1
+        self.state.held_frames(control) == 1
+    }
+
+    /// Is this input being clicked up?
+    /// i.e. was it down last frame, but up this frame?
+    fn clicked_up(&self, control: C) -> bool {
+        self.state.clicked_up(control)
+    }
+
+    /// How many frames in a row has this input been pressed down?
+    fn held_frames(&self, control: C) -> u32 {
+        self.state.held_frames(control)
+    }
+
+    /// How many frames was this input held down for, the last time it was released?
+    fn last_press_frames(&self, control: C) -> u32 {
+        self.state.last_press_frames(control)
+    }
+
+    /// How far is this axis being pushed?
+    fn axis(&self, control: C) -> f32 {
+        self.axes.value(control, &self.analog_values, |c| {
+            self.state.held_frames(c) > 0
+        })
+    }
+
+    /// Combine two axes into a vector, like for a stick or WASD movement.
+    fn axis2(&self, x: C, y: C) -> Vec2<f32> {
+        self.axes
+            .value2(x, y, &self.analog_values, |c| self.state.held_frames(c) > 0)
+    }
+
+    /// How this input repeats when you call `repeats`.
+    fn repeat_profile(&self, control: C) -> RepeatProfile {
+        self.state.repeat_profile(control)
+    }
+
+    /// Everything that's happened to the controls recently.
+    fn history(&self) -> &InputHistory<C> {
+        self.state.history()
+    }
+
+    /// Like `buffered`, but if it was, use up the press so it won't count again.
+    fn consume_buffered(&mut self, control: C, frames: u64) -> bool {
+        self.state.history_mut().consume_buffered(control, frames)
     }
 }
 
-/// EnumMap doesn't implement Clone so we do it ourselves
-impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Enum<bool> + Clone> Clone
-    for EventInputHandler<I, C>
-{
-    fn clone(&self) -> Self {
-        let control_config = self.control_config.clone();
-        let listening_for_input = self.listening_for_input.clone();
+#[test]
+fn tap_and_hold() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Attack,
+    }
 
-        let mut pressed_controls = EnumMap::default();
-        for (k, v) in self.pressed_controls.iter() {
-            pressed_controls[k] = *v;
-        }
+    let mut handler = EventInputHandler::new_empty();
+    handler.bindings_mut().bind('z', Control::Attack);
 
-        let mut input_time = EnumMap::default();
-        for (k, v) in self.input_time.iter() {
-            input_time[k] = *v;
-        }
+    handler.input_down('z');
+    handler.update();
+    handler.input_up('z');
+    handler.update();
+    assert!(handler.clicked_up(Control::Attack));
+    assert_eq!(handler.last_press_frames(Control::Attack), 1);
+    handler.update();
+    assert!(!handler.clicked_up(Control::Attack));
+    assert_eq!(handler.last_press_frames(Control::Attack), 1);
 
-        Self {
-            control_config,
-            input_time,
-            listening_for_input,
-            pressed_controls,
-        }
+    handler.input_down('z');
+    for frame in 1..=30 {
+        handler.update();
+        assert_eq!(handler.held_frames(Control::Attack), frame);
     }
+    assert!(handler.held_for(Control::Attack, 30));
+    assert!(!handler.held_for(Control::Attack, 31));
+    handler.input_up('z');
+    handler.update();
+    assert!(handler.clicked_up(Control::Attack));
+    assert!(!handler.held_for(Control::Attack, 0));
+    assert_eq!(handler.last_press_frames(Control::Attack), 30);
 }
@@ -0,0 +1,395 @@
+use std::collections::VecDeque;
+
+use enum_map::Enum;
+
+use crate::grids::{Direction8, ICoord, Vec2};
+
+use super::bindings::same_control;
+
+/// How far a pair of direction axes have to be pushed to count as pointing in a direction.
+const DIRECTION_THRESHOLD: f32 = 0.5;
+
+/// Something that happened to the controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputEvent<C> {
+    /// A control was clicked down.
+    Pressed(C),
+    /// A control was clicked up.
+    Released(C),
+    /// The direction axes started pointing in a new direction, or `None` for the middle.
+    Direction(Option<Direction8>),
+}
+
+/// An [`InputEvent`], and the frame it happened on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InputRecord<C> {
+    pub event: InputEvent<C>,
+    /// Which frame this happened on. The first call to `update` is frame 1.
+    pub frame: u64,
+    /// Whether this press has been used up by `consume_buffered`.
+    pub consumed: bool,
+}
+
+/**
+A record of the most recent things that happened to the controls.
+
+It only keeps so many events, forgetting the oldest ones when it runs out of room.
+By default it keeps 128.
+
+Each input handler keeps one of these, and you can get it with
+[`InputHandler::history`](super::InputHandler::history).
+*/
+#[derive(Debug, Clone)]
+pub struct InputHistory<C> {
+    records: VecDeque<InputRecord<C>>,
+    capacity: usize,
+    frame: u64,
+    direction: Option<Direction8>,
+}
+
+impl<C: Enum<u32> + Clone> InputHistory<C> {
+    pub(crate) fn new() -> Self {
+        Self::with_capacity(128)
+    }
+
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+            frame: 0,
+            direction: None,
+        }
+    }
+
+    /// How many frames have been recorded. This is 0 before the first `update`.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// The direction the direction axes are pointing in, if any.
+    pub fn direction(&self) -> Option<Direction8> {
+        self.direction
+    }
+
+    /// How many events it remembers at most.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// How many events it remembers right now.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterate over the events it remembers, from newest to oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &InputRecord<C>> + '_ {
+        self.records.iter().rev()
+    }
+
+    /// Which frame was this control last clicked down on, if it remembers?
+    pub fn last_pressed(&self, control: C) -> Option<u64> {
+        self.iter()
+            .find(|record| matches!(&record.event, InputEvent::Pressed(c) if same_control(c, &control)))
+            .map(|record| record.frame)
+    }
+
+    /// Which frame was this control last clicked up on, if it remembers?
+    pub fn last_released(&self, control: C) -> Option<u64> {
+        self.iter()
+            .find(|record| matches!(&record.event, InputEvent::Released(c) if same_control(c, &control)))
+            .map(|record| record.frame)
+    }
+
+    /// Was this control clicked down within the last `frames` frames, without being consumed?
+    ///
+    /// Within the last 1 frame means on this frame.
+    pub fn buffered(&self, control: C, frames: u64) -> bool {
+        self.buffered_idx(control, frames).is_some()
+    }
+
+    /// Is the sequence finishing on this frame?
+    pub fn performed(&self, sequence: &Sequence<C>) -> bool {
+        // Match the steps backwards, starting from the newest event
+        let mut remaining = sequence.steps.len();
+        let earliest = (self.frame + 1).saturating_sub(sequence.window);
+        for record in self.iter() {
+            if remaining == 0 || record.frame < earliest {
+                break;
+            }
+            // The last step has to be what just happened
+            if remaining == sequence.steps.len() && record.frame != self.frame {
+                return false;
+            }
+            if sequence.steps[remaining - 1].matches(&record.event) {
+                remaining -= 1;
+            }
+        }
+        remaining == 0
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.records.len() > capacity {
+            self.records.pop_front();
+        }
+    }
+
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub(crate) fn record(&mut self, event: InputEvent<C>) {
+        let idx = self.records.len();
+        self.record_at(idx, event);
+    }
+
+    /// Record the direction axes pointing this way, if that's a change.
+    ///
+    /// Directions go before everything else that happened on the same frame,
+    /// so pressing a button right as the stick gets somewhere counts as doing it in that order.
+    pub(crate) fn record_direction(&mut self, axes: Vec2<f32>) {
+        let direction = direction_of(axes);
+        if direction != self.direction {
+            self.direction = direction;
+            let idx = self
+                .records
+                .iter()
+                .rposition(|record| record.frame < self.frame)
+                .map_or(0, |idx| idx + 1);
+            self.record_at(idx, InputEvent::Direction(direction));
+        }
+    }
+
+    fn record_at(&mut self, mut idx: usize, event: InputEvent<C>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() >= self.capacity {
+            self.records.pop_front();
+            idx = idx.saturating_sub(1);
+        }
+        self.records.insert(
+            idx,
+            InputRecord {
+                event,
+                frame: self.frame,
+                consumed: false,
+            },
+        );
+    }
+
+    pub(crate) fn consume_buffered(&mut self, control: C, frames: u64) -> bool {
+        match self.buffered_idx(control, frames) {
+            Some(idx) => {
+                self.records[idx].consumed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The index of the newest unconsumed press of the control within the window.
+    fn buffered_idx(&self, control: C, frames: u64) -> Option<usize> {
+        let earliest = (self.frame + 1).saturating_sub(frames);
+        self.records
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, record)| record.frame >= earliest)
+            .find(|(_, record)| {
+                !record.consumed
+                    && matches!(&record.event, InputEvent::Pressed(c) if same_control(c, &control))
+            })
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// One step in a [`Sequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SequenceStep<C> {
+    /// Start pointing the direction axes this way.
+    Direction(Direction8),
+    /// Click this control down.
+    Press(C),
+}
+
+impl<C: Enum<u32> + Clone> SequenceStep<C> {
+    fn matches(&self, event: &InputEvent<C>) -> bool {
+        match (self, event) {
+            (SequenceStep::Direction(dir), InputEvent::Direction(Some(happened))) => {
+                dir == happened
+            }
+            (SequenceStep::Press(control), InputEvent::Pressed(happened)) => {
+                same_control(control, happened)
+            }
+            _ => false,
+        }
+    }
+}
+
+/**
+A series of inputs that have to happen in order within a certain number of frames,
+like a special move in a fighting game.
+
+Other inputs can happen in between the steps, so the player doesn't have to be perfectly precise.
+
+Directions come from the handler's direction axes (see `set_direction_axes` on the handlers),
+so they work the same with a stick or the keyboard.
+
+```
+# use cogs_gamedev::controls::{InputHandler, PollingInputHandler, Sequence, SequenceStep};
+# use cogs_gamedev::grids::Direction8;
+# use enum_map::Enum;
+# use std::collections::HashSet;
+#[derive(Enum, Clone, Copy, Debug, PartialEq)]
+enum Control {
+    Left,
+    Right,
+    Up,
+    Down,
+    MoveX,
+    MoveY,
+    Punch,
+}
+
+let mut handler = PollingInputHandler::new_empty();
+for (key, control) in [
+    ('a', Control::Left),
+    ('d', Control::Right),
+    ('w', Control::Up),
+    ('s', Control::Down),
+    ('j', Control::Punch),
+].iter() {
+    handler.bindings_mut().bind(*key, *control);
+}
+handler.axes_mut().bind_virtual(Control::Left, Control::Right, Control::MoveX);
+handler.axes_mut().bind_virtual(Control::Up, Control::Down, Control::MoveY);
+handler.set_direction_axes(Control::MoveX, Control::MoveY);
+
+// Quarter-circle forward + punch
+let hadouken = Sequence::new(
+    vec![
+        SequenceStep::Direction(Direction8::South),
+        SequenceStep::Direction(Direction8::SouthEast),
+        SequenceStep::Direction(Direction8::East),
+        SequenceStep::Press(Control::Punch),
+    ],
+    20,
+);
+
+let frames = ["", "s", "s", "sd", "d", "d", "dj", "dj"];
+let performed: Vec<bool> = frames
+    .iter()
+    .map(|keys| {
+        let held: HashSet<char> = keys.chars().collect();
+        handler.update(&held);
+        handler.performed(&hadouken)
+    })
+    .collect();
+assert_eq!(performed, vec![false, false, false, false, false, false, true, false]);
+
+// For when the character is facing the other way
+let backwards = hadouken.mirrored();
+assert_eq!(backwards.steps()[2], SequenceStep::Direction(Direction8::West));
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sequence<C> {
+    steps: Vec<SequenceStep<C>>,
+    window: u64,
+}
+
+impl<C: Enum<u32> + Clone> Sequence<C> {
+    /// Make a sequence that has to be done within `window` frames, counting the frame it finishes on.
+    ///
+    /// Panics if there aren't any steps.
+    pub fn new(steps: Vec<SequenceStep<C>>, window: u64) -> Self {
+        assert_ne!(steps.len(), 0, "A sequence needs at least one step!");
+        Self { steps, window }
+    }
+
+    pub fn steps(&self) -> &[SequenceStep<C>] {
+        &self.steps
+    }
+
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Flip all the directions left-to-right, for when a character turns around.
+    pub fn mirrored(&self) -> Self {
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| match step {
+                SequenceStep::Direction(dir) => {
+                    let deltas = dir.deltas();
+                    SequenceStep::Direction(
+                        Direction8::from_deltas(ICoord::new(-deltas.x, deltas.y)).unwrap(),
+                    )
+                }
+                SequenceStep::Press(control) => SequenceStep::Press(control.clone()),
+            })
+            .collect();
+        Self {
+            steps,
+            window: self.window,
+        }
+    }
+}
+
+/// Which way a pair of axes is pointing, if they're pushed far enough.
+fn direction_of(axes: Vec2<f32>) -> Option<Direction8> {
+    if axes.length() < DIRECTION_THRESHOLD {
+        return None;
+    }
+    // y is down, so going clockwise from east
+    let eighths = (axes.angle() / std::f32::consts::FRAC_PI_4).round() as isize;
+    Some(Direction8::DIRECTIONS[(2 + eighths).rem_euclid(8) as usize])
+}
+
+#[test]
+fn buffering() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Jump,
+        Attack,
+    }
+
+    let mut history = InputHistory::with_capacity(4);
+    for _ in 0..3 {
+        history.next_frame();
+    }
+    history.record(InputEvent::Pressed(Control::Jump));
+    for _ in 0..5 {
+        history.next_frame();
+    }
+    // Pressed on frame 3, now it's frame 8
+    assert_eq!(history.last_pressed(Control::Jump), Some(3));
+    assert!(history.buffered(Control::Jump, 6));
+    assert!(!history.buffered(Control::Jump, 5));
+    assert!(!history.buffered(Control::Attack, 6));
+
+    assert!(history.consume_buffered(Control::Jump, 6));
+    assert!(!history.consume_buffered(Control::Jump, 6));
+    assert_eq!(history.last_pressed(Control::Jump), Some(3));
+
+    // It's a ring buffer
+    for _ in 0..4 {
+        history.record(InputEvent::Released(Control::Attack));
+    }
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.last_pressed(Control::Jump), None);
+
+    assert_eq!(direction_of(Vec2::new(0.1, 0.1)), None);
+    assert_eq!(direction_of(Vec2::new(0.0, 1.0)), Some(Direction8::South));
+    assert_eq!(
+        direction_of(Vec2::new(-0.7, -0.7)),
+        Some(Direction8::NorthWest)
+    );
+    assert_eq!(direction_of(Vec2::new(-1.0, 0.1)), Some(Direction8::West));
+}
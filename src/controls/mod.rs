@@ -69,25 +69,60 @@
 //!
 //! ```
 //!
+//! # Bindings
+//!
+//! Which inputs activate which controls is stored in a `Bindings`. Each control can have several bindings
+//! (say, a key on the keyboard and a button on a gamepad), a binding can be a chord of several inputs
+//! held at once (like Ctrl+S), and one input can activate several controls.
+//! Get at them with `bindings` and `bindings_mut` on either handler.
+//!
+//! # Analog Input
+//!
+//! Controls can also be used as axes with an `f32` value, for sticks and triggers.
+//! An axis can be bound to analog inputs (pass their values to the handler with `set_analog`),
+//! or to two digital controls as a "virtual axis", so the arrow keys and a stick can move the player
+//! with the same code. Set them up with `axes_mut` on either handler, and query them with
+//! `InputHandler::axis` and `InputHandler::axis2`.
+//!
+//! # History
+//!
+//! Both handlers remember the last few things that happened to the controls in an `InputHistory`.
+//! This lets you buffer inputs with `InputHandler::buffered`, and check for fighting-game style
+//! motions with `InputHandler::performed`. To use directions in those motions, tell the handler
+//! which axes to get them from with `set_direction_axes`.
+//!
 //! # Changing Controls on the Fly
 //!
 //! Both input handlers support changing controls on the fly (perhaps through some sort of menu).
 //! Call `listen_for_control_change` with the control you want to update the input for, and the next time an input
-//! is received, that control will be associated with that input.
+//! is received, that input will replace all of that control's bindings.
+//! To only replace one of its bindings (perhaps the keyboard one, leaving the gamepad one alone),
+//! call `listen_for_binding_change` instead.
 //!
-//! If multiple controls are pressed at the same time during a frame where the input handler is
+//! If multiple inputs are pressed at the same time during a frame where the input handler is
 //! listening for a control change, it's undefined which one the control will be set to.
 //! It will be set to one of them, however.
 
+mod axes;
+pub use axes::{Axes, AxisBinding, AxisSettings, Deadzone, ResponseCurve};
+mod bindings;
+pub use bindings::{Binding, Bindings};
 mod polling;
 pub use polling::PollingInputHandler;
 mod event;
 pub use event::EventInputHandler;
+mod history;
+pub use history::{InputEvent, InputHistory, InputRecord, Sequence, SequenceStep};
+
+mod state;
+pub use state::RepeatProfile;
 
 use std::hash::Hash;
 
 use enum_map::Enum;
 
+use crate::grids::Vec2;
+
 /// The InputHandler trait, makng sure that both styles of input handling
 /// expose the same API.
 pub trait InputHandler<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> {
@@ -102,4 +137,74 @@ pub trait InputHandler<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> {
     /// Is this input being clicked down?
     /// i.e. was it up last frame, but down this frame?
     fn clicked_down(&self, control: C) -> bool;
+
+    /// Is this input being clicked up?
+    /// i.e. was it down last frame, but up this frame?
+    fn clicked_up(&self, control: C) -> bool;
+
+    /// How many frames in a row has this input been pressed down?
+    /// This is 0 if it's released, and 1 on the frame it's clicked down.
+    fn held_frames(&self, control: C) -> u32;
+
+    /// Has this input been pressed down for at least this many frames?
+    fn held_for(&self, control: C, frames: u32) -> bool {
+        let held = self.held_frames(control);
+        held > 0 && held >= frames
+    }
+
+    /// How many frames was this input held down for, the last time it was released?
+    /// This is 0 if it's never been released.
+    ///
+    /// On the frame the input is clicked up, this is how long that press was,
+    /// so you can tell a quick tap from a long hold.
+    fn last_press_frames(&self, control: C) -> u32;
+
+    /// How far is this axis being pushed?
+    /// This is from -1 to 1 (or 0 to 1 for things like triggers), after the deadzone and response curve.
+    ///
+    /// Controls without any axis bindings are always 0.
+    fn axis(&self, control: C) -> f32;
+
+    /// Combine two axes into a vector, like for a stick or WASD movement.
+    ///
+    /// It's never longer than 1, so moving diagonally isn't any faster.
+    /// If both axes use a radial deadzone, the x axis's response curve is used for the whole vector.
+    fn axis2(&self, x: C, y: C) -> Vec2<f32>;
+
+    /// Is this input firing this frame, as though it were a key repeating in a text box?
+    /// This is true on the frame it's clicked down, then again after it's been held `delay` more frames,
+    /// then every `interval` frames after that.
+    ///
+    /// Good for scrolling through menus.
+    fn repeating(&self, control: C, delay: u32, interval: u32) -> bool {
+        RepeatProfile::new(delay, interval).fires(self.held_frames(control))
+    }
+
+    /// How this input repeats when you call `repeats`.
+    fn repeat_profile(&self, control: C) -> RepeatProfile;
+
+    /// Like `repeating`, but with the delay and interval from this input's repeat profile.
+    fn repeats(&self, control: C) -> bool {
+        let profile = self.repeat_profile(control.clone());
+        profile.fires(self.held_frames(control))
+    }
+
+    /// Everything that's happened to the controls recently.
+    fn history(&self) -> &InputHistory<C>;
+
+    /// Was this input clicked down within the last `frames` frames, without being consumed?
+    /// Within the last 1 frame means on this frame.
+    ///
+    /// Good for jump buffering: if the player presses jump just before landing, they should still jump.
+    fn buffered(&self, control: C, frames: u64) -> bool {
+        self.history().buffered(control, frames)
+    }
+
+    /// Like `buffered`, but if it was, use up the press so it won't count again.
+    fn consume_buffered(&mut self, control: C, frames: u64) -> bool;
+
+    /// Did the player just finish doing this sequence of inputs?
+    fn performed(&self, sequence: &Sequence<C>) -> bool {
+        self.history().performed(sequence)
+    }
 }
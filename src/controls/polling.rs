@@ -1,71 +1,154 @@
 use std::{collections::HashSet, hash::Hash};
 
 use ahash::AHashMap;
-use enum_map::{Enum, EnumMap};
+use enum_map::Enum;
 
-use super::InputHandler;
+use super::{
+    bindings::Listening, state::ControlState, Axes, Binding, Bindings, InputHandler, InputHistory,
+    RepeatProfile,
+};
+use crate::grids::Vec2;
 
 /// Polling-based input handler.
 /// See module-level documentation for more.
+#[derive(Clone)]
 pub struct PollingInputHandler<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> {
     /// Maps inputs to the controls they activate
-    control_config: AHashMap<I, C>,
-    /// How long each control has been pressed
-    input_time: EnumMap<C, u32>,
+    control_config: Bindings<I, C>,
+    /// How long each control has been pressed, and so on
+    state: ControlState<C>,
     /// If this is Some, we're waiting for a new control config.
-    listening_for_input: Option<C>,
+    listening_for_input: Option<Listening<I, C>>,
+    /// Maps analog inputs and virtual axes to the controls they move
+    axes: Axes<I, C>,
+    /// The value of each analog input as of the last `update`
+    analog_values: AHashMap<I, f32>,
+    /// The value of each analog input, as we've been told since the last `update`
+    new_analog_values: AHashMap<I, f32>,
+    /// The axes to record directions in the history from
+    direction_axes: Option<(C, C)>,
 }
 
 impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> PollingInputHandler<I, C> {
     /// Create a new PollingInputHandler without any controls.
     pub fn new_empty() -> Self {
-        Self {
-            control_config: AHashMap::new(),
-            // conveniently, the default value for u32 is 0!
-            // and we want the map to start full of zeros.
-            // (zeroes?)
-            input_time: EnumMap::default(),
-            listening_for_input: None,
-        }
+        Self::with_bindings(Bindings::new())
     }
 
     /// Create a new PollingInputHandler with the specified controls.
     /// The HashMap in should map inputs to the controls you want them to actuate.
     pub fn new(control_config: AHashMap<I, C>) -> Self {
+        Self::with_bindings(control_config.into())
+    }
+
+    /// Create a new PollingInputHandler with the specified bindings.
+    pub fn with_bindings(control_config: Bindings<I, C>) -> Self {
         Self {
             control_config,
-            input_time: EnumMap::default(),
+            state: ControlState::new(),
             listening_for_input: None,
+            axes: Axes::new(),
+            analog_values: AHashMap::new(),
+            new_analog_values: AHashMap::new(),
+            direction_axes: None,
         }
     }
 
     /// Update the input handler. You MUST CALL THIS FIRST THING in your game loop.
     /// Otherwise things won't get updated correctly.
     pub fn update(&mut self, new_inputs: &HashSet<I>) {
-        match &self.listening_for_input {
+        self.analog_values.clone_from(&self.new_analog_values);
+
+        match self.listening_for_input.take() {
             None => {
-                for (input, control) in self.control_config.iter() {
-                    if new_inputs.contains(input) {
-                        // this input is getting pressed!
-                        // increment our timer
-                        self.input_time[control.to_owned()] += 1;
-                    } else {
-                        // this input is not getting pressed
-                        // reset our timer
-                        self.input_time[control.to_owned()] = 0;
-                    }
+                let active = self
+                    .control_config
+                    .active(|input| new_inputs.contains(input));
+                self.state.update(&active);
+                if let Some((x, y)) = &self.direction_axes {
+                    let state = &self.state;
+                    let direction =
+                        self.axes
+                            .value2(x.clone(), y.clone(), &self.analog_values, |c| {
+                                state.held_frames(c) > 0
+                            });
+                    self.state.history_mut().record_direction(direction);
                 }
             }
-            Some(ctrl) => {
+            Some(listening) => {
                 if let Some(input) = new_inputs.iter().next() {
                     // we're pressing something!
-                    self.control_config
-                        .insert(input.to_owned(), ctrl.to_owned());
-                    self.listening_for_input = None;
+                    listening.rebind(&mut self.control_config, input.to_owned());
+                } else {
+                    self.listening_for_input = Some(listening);
                 }
             }
         }
     }
+
+    /// The next time an input is pressed, make it the only binding for this control.
+    pub fn listen_for_control_change(&mut self, control: C) {
+        self.listening_for_input = Some(Listening::new(control, None));
+    }
+
+    /// The next time an input is pressed, bind it to this control in place of `old`.
+    /// The control's other bindings are left alone.
+    pub fn listen_for_binding_change(&mut self, control: C, old: Binding<I>) {
+        self.listening_for_input = Some(Listening::new(control, Some(old)));
+    }
+
+    /// Is the handler waiting for an input to change a control to?
+    pub fn is_listening(&self) -> bool {
+        self.listening_for_input.is_some()
+    }
+
+    /// Stop waiting for an input to change a control to.
+    pub fn stop_listening(&mut self) {
+        self.listening_for_input = None;
+    }
+
+    /// Get the bindings from inputs to controls.
+    pub fn bindings(&self) -> &Bindings<I, C> {
+        &self.control_config
+    }
+
+    /// Get the bindings from inputs to controls, so you can change them.
+    pub fn bindings_mut(&mut self) -> &mut Bindings<I, C> {
+        &mut self.control_config
+    }
+
+    /// Tell the handler the value of an analog input, like how far a stick or trigger is pushed.
+    /// It stays at that value until you give it another one.
+    ///
+    /// This takes effect the next time you call `update`.
+    pub fn set_analog(&mut self, input: I, value: f32) {
+        self.new_analog_values.insert(input, value);
+    }
+
+    /// Get the bindings for analog inputs and virtual axes.
+    pub fn axes(&self) -> &Axes<I, C> {
+        &self.axes
+    }
+
+    /// Get the bindings for analog inputs and virtual axes, so you can change them.
+    pub fn axes_mut(&mut self) -> &mut Axes<I, C> {
+        &mut self.axes
+    }
+
+    /// Change how this control repeats when you call `repeats`.
+    pub fn set_repeat_profile(&mut self, control: C, profile: RepeatProfile) {
+        self.state.set_repeat_profile(control, profile);
+    }
+
+    /// Record which way these axes point in the history, so they can be used in `Sequence`s.
+    pub fn set_direction_axes(&mut self, x: C, y: C) {
+        self.direction_axes = Some((x, y));
+    }
+
+    /// Change how many events the history remembers.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.state.history_mut().set_capacity(capacity);
+    }
 }
 
 // there's gotta be a better way to do these generics
@@ -75,37 +158,138 @@ impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> InputHandler<I, C>
     /// Is this input pressed down?
     /// i.e. is the player pressing the button?
     fn pressed(&self, control: C) -> bool {
-        self.input_time[control] >= 1
+        self.state.held_frames(control) >= 1
     }
 
     /// Is this input released?
     /// i.e. is the player *not* pressing the button?
     fn released(&self, control: C) -> bool {
-        self.input_time[control] == 0
+        self.state.held_frames(control) == 0
     }
 
     /// Is this input being clicked down?
     /// i.e. was it up last frame, but down this frame?
     fn clicked_down(&self, control: C) -> bool {
-        self.input_time[control] == 1
+        self.state.held_frames(control) == 1
+    }
+
+    /// Is this input being clicked up?
+    /// i.e. was it down last frame, but up this frame?
+    fn clicked_up(&self, control: C) -> bool {
+        self.state.clicked_up(control)
+    }
+
+    /// How many frames in a row has this input been pressed down?
+    fn held_frames(&self, control: C) -> u32 {
+        self.state.held_frames(control)
+    }
+
+    /// How many frames was this input held down for, the last time it was released?
+    fn last_press_frames(&self, control: C) -> u32 {
+        self.state.last_press_frames(control)
+    }
+
+    /// How far is this axis being pushed?
+    fn axis(&self, control: C) -> f32 {
+        self.axes.value(control, &self.analog_values, |c| {
+            self.state.held_frames(c) > 0
+        })
+    }
+
+    /// Combine two axes into a vector, like for a stick or WASD movement.
+    fn axis2(&self, x: C, y: C) -> Vec2<f32> {
+        self.axes
+            .value2(x, y, &self.analog_values, |c| self.state.held_frames(c) > 0)
+    }
+
+    /// How this input repeats when you call `repeats`.
+    fn repeat_profile(&self, control: C) -> RepeatProfile {
+        self.state.repeat_profile(control)
+    }
+
+    /// Everything that's happened to the controls recently.
+    fn history(&self) -> &InputHistory<C> {
+        self.state.history()
+    }
+
+    /// Like `buffered`, but if it was, use up the press so it won't count again.
+    fn consume_buffered(&mut self, control: C, frames: u64) -> bool {
+        self.state.history_mut().consume_buffered(control, frames)
     }
 }
 
-/// EnumMap doesn't implement Clone so we do it ourselves
-impl<I: Hash + Eq + PartialEq + Clone, C: Enum<u32> + Clone> Clone for PollingInputHandler<I, C> {
-    fn clone(&self) -> Self {
-        let control_config = self.control_config.clone();
-        let listening_for_input = self.listening_for_input.clone();
+#[test]
+fn rebinding() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Jump,
+        Fire,
+    }
 
-        let mut input_time = EnumMap::default();
-        for (k, v) in self.input_time.iter() {
-            input_time[k] = *v;
-        }
+    let mut handler = PollingInputHandler::new_empty();
+    handler.bindings_mut().bind("space", Control::Jump);
+    handler.bindings_mut().bind("a", Control::Jump);
+    handler.bindings_mut().bind("ctrl", Control::Fire);
 
-        Self {
-            control_config,
-            input_time,
-            listening_for_input,
+    // Letting go of one of the inputs doesn't let go of the control
+    let held = |inputs: &[&'static str]| inputs.iter().copied().collect::<HashSet<_>>();
+    handler.update(&held(&["space", "a"]));
+    handler.update(&held(&["a"]));
+    assert!(handler.pressed(Control::Jump));
+    assert!(!handler.clicked_down(Control::Jump));
+
+    handler.listen_for_control_change(Control::Jump);
+    handler.update(&held(&[]));
+    assert!(handler.is_listening());
+    handler.update(&held(&["w"]));
+    assert!(!handler.is_listening());
+
+    // The old bindings are gone
+    handler.update(&held(&["space", "a"]));
+    assert!(handler.released(Control::Jump));
+    handler.update(&held(&["w"]));
+    assert!(handler.clicked_down(Control::Jump));
+
+    // Only replace one binding
+    handler.bindings_mut().bind("b", Control::Fire);
+    handler.listen_for_binding_change(Control::Fire, Binding::new("ctrl"));
+    handler.update(&held(&["x"]));
+    let fire: Vec<_> = handler.bindings().bindings_for(Control::Fire).collect();
+    assert_eq!(fire, vec![&Binding::new("b"), &Binding::new("x")]);
+}
+
+#[test]
+fn repeating_menus() {
+    #[derive(Enum, Clone, Copy, Debug)]
+    enum Control {
+        Up,
+        Down,
+    }
+
+    let mut handler = PollingInputHandler::new_empty();
+    handler.bindings_mut().bind("up", Control::Up);
+    handler.bindings_mut().bind("down", Control::Down);
+    handler.set_repeat_profile(Control::Down, RepeatProfile::new(3, 2));
+
+    let held: HashSet<_> = ["up", "down"].iter().copied().collect();
+    let mut up = Vec::new();
+    let mut down = Vec::new();
+    for frame in 1..=30 {
+        handler.update(&held);
+        if handler.repeats(Control::Up) {
+            up.push(frame);
+        }
+        if handler.repeats(Control::Down) {
+            down.push(frame);
         }
+        assert_eq!(
+            handler.repeats(Control::Down),
+            handler.repeating(Control::Down, 3, 2)
+        );
     }
+    assert_eq!(up, vec![1, 21, 26]);
+    assert_eq!(
+        down,
+        vec![1, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22, 24, 26, 28, 30]
+    );
 }
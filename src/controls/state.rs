@@ -0,0 +1,166 @@
+use enum_map::{Enum, EnumMap};
+
+use super::{bindings::same_control, InputEvent, InputHistory};
+
+/**
+How a held control repeats, like holding down a key in a text box.
+It fires on the frame it's pressed, then once more after `delay` frames,
+then every `interval` frames after that for as long as it's held.
+
+```
+# use cogs_gamedev::controls::RepeatProfile;
+let profile = RepeatProfile::new(10, 4);
+let fires: Vec<u32> = (1..=30).filter(|&held| profile.fires(held)).collect();
+assert_eq!(fires, vec![1, 11, 15, 19, 23, 27]);
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RepeatProfile {
+    /// How many frames after the first press before it starts repeating
+    pub delay: u32,
+    /// How many frames between each repeat
+    pub interval: u32,
+}
+
+impl RepeatProfile {
+    pub fn new(delay: u32, interval: u32) -> Self {
+        Self { delay, interval }
+    }
+
+    /// Does a control that's been held for this many frames fire this frame?
+    ///
+    /// An interval of 0 is treated like 1, firing every frame.
+    pub fn fires(&self, held_frames: u32) -> bool {
+        match held_frames {
+            0 => false,
+            1 => true,
+            held => {
+                let since_press = held - 1;
+                // Not `is_multiple_of`, which needs Rust 1.87 (this crate supports 1.71 and up)
+                since_press >= self.delay && (since_press - self.delay) % self.interval.max(1) == 0
+            }
+        }
+    }
+}
+
+/// Waits 20 frames, then repeats every 5 frames.
+/// At 60 FPS, that's a third of a second, then 12 times a second.
+impl Default for RepeatProfile {
+    fn default() -> Self {
+        Self::new(20, 5)
+    }
+}
+
+/// The state of every control, and how it's changed over time.
+/// Both input handlers keep one of these, so they work exactly the same way.
+pub(crate) struct ControlState<C: Enum<u32> + Clone> {
+    /// How long each control has been pressed
+    input_time: EnumMap<C, u32>,
+    /// How long each control had been pressed as of last frame
+    last_input_time: EnumMap<C, u32>,
+    /// How long each control was held the last time it was released
+    last_press: EnumMap<C, u32>,
+    /// Repeat profiles for controls that don't use the default one
+    repeat_profiles: Vec<(C, RepeatProfile)>,
+    /// Everything that's happened recently
+    history: InputHistory<C>,
+}
+
+impl<C: Enum<u32> + Clone> ControlState<C> {
+    pub(crate) fn new() -> Self {
+        Self {
+            // conveniently, the default value for u32 is 0!
+            // and we want the maps to start full of zeros.
+            // (zeroes?)
+            input_time: EnumMap::default(),
+            last_input_time: EnumMap::default(),
+            last_press: EnumMap::default(),
+            repeat_profiles: Vec::new(),
+            history: InputHistory::new(),
+        }
+    }
+
+    /// Move on to the next frame, given how many bindings are active for each control.
+    pub(crate) fn update(&mut self, active: &EnumMap<C, u32>) {
+        self.history.next_frame();
+        for (control, time) in self.input_time.iter_mut() {
+            self.last_input_time[control.clone()] = *time;
+            if active[control.clone()] > 0 {
+                // this control is getting pressed!
+                // increment our timer
+                *time += 1;
+                if *time == 1 {
+                    self.history.record(InputEvent::Pressed(control));
+                }
+            } else {
+                // this control is not getting pressed
+                // remember how long it was held for, and reset our timer
+                if *time > 0 {
+                    self.last_press[control.clone()] = *time;
+                    self.history.record(InputEvent::Released(control));
+                }
+                *time = 0;
+            }
+        }
+    }
+
+    pub(crate) fn held_frames(&self, control: C) -> u32 {
+        self.input_time[control]
+    }
+
+    pub(crate) fn clicked_up(&self, control: C) -> bool {
+        self.input_time[control.clone()] == 0 && self.last_input_time[control] > 0
+    }
+
+    pub(crate) fn last_press_frames(&self, control: C) -> u32 {
+        self.last_press[control]
+    }
+
+    pub(crate) fn history(&self) -> &InputHistory<C> {
+        &self.history
+    }
+
+    pub(crate) fn history_mut(&mut self) -> &mut InputHistory<C> {
+        &mut self.history
+    }
+
+    pub(crate) fn repeat_profile(&self, control: C) -> RepeatProfile {
+        self.repeat_profiles
+            .iter()
+            .find(|(c, _)| same_control(c, &control))
+            .map(|(_, profile)| *profile)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_repeat_profile(&mut self, control: C, profile: RepeatProfile) {
+        match self
+            .repeat_profiles
+            .iter_mut()
+            .find(|(c, _)| same_control(c, &control))
+        {
+            Some((_, old)) => *old = profile,
+            None => self.repeat_profiles.push((control, profile)),
+        }
+    }
+}
+
+/// EnumMap doesn't implement Clone so we do it ourselves
+impl<C: Enum<u32> + Clone> Clone for ControlState<C> {
+    fn clone(&self) -> Self {
+        Self {
+            input_time: copy_map(&self.input_time),
+            last_input_time: copy_map(&self.last_input_time),
+            last_press: copy_map(&self.last_press),
+            repeat_profiles: self.repeat_profiles.clone(),
+            history: self.history.clone(),
+        }
+    }
+}
+
+fn copy_map<C: Enum<u32>>(map: &EnumMap<C, u32>) -> EnumMap<C, u32> {
+    let mut out = EnumMap::default();
+    for (k, v) in map.iter() {
+        out[k] = *v;
+    }
+    out
+}
@@ -0,0 +1,321 @@
+//! Cellular automata, for generating caves and other squiggly things.
+
+use super::{Grid, ICoord};
+
+use std::{fmt::Display, str::FromStr};
+
+/// Which cells count as neighbors of a cell.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Neighborhood {
+    /// The 4 orthagonal neighbors, in the order of [`ICoord::neighbors4`].
+    VonNeumann,
+    /// All 8 neighbors, in the order of [`ICoord::neighbors8`].
+    Moore,
+}
+
+/// What to do about neighbors that are off the edge of the grid.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EdgeMode<T> {
+    /// Wrap around to the other side, like the grid is a torus.
+    Wrap,
+    /// Use the nearest cell that's on the grid.
+    Clamp,
+    /// Pretend everything off the grid is this value.
+    Constant(T),
+}
+
+/**
+Steps a cellular automaton forward on a double-buffered [`Grid`].
+
+Each step, every cell's new value is decided by a rule looking at its old value and its
+neighbors' old values. None of the new values are visible until the whole step is done.
+
+```
+# use cogs_gamedev::grids::{Automaton, EdgeMode, Grid, LifeRule, Neighborhood};
+// A blinker
+let grid = Grid::from_vec(3, 3, vec![
+    false, true, false,
+    false, true, false,
+    false, true, false,
+]).unwrap();
+let mut life = Automaton::new(grid.clone(), Neighborhood::Moore, EdgeMode::Constant(false));
+let rule: LifeRule = "B3/S23".parse().unwrap();
+
+life.step_life(&rule);
+assert_eq!(life.grid().as_slice(), &[
+    false, false, false,
+    true, true, true,
+    false, false, false,
+]);
+life.step_life(&rule);
+assert_eq!(life.grid(), &grid);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct Automaton<T> {
+    front: Grid<T>,
+    back: Grid<T>,
+    neighborhood: Neighborhood,
+    edges: EdgeMode<T>,
+}
+
+impl<T: Clone> Automaton<T> {
+    /// Make a new automaton, starting from the given grid.
+    pub fn new(grid: Grid<T>, neighborhood: Neighborhood, edges: EdgeMode<T>) -> Self {
+        Self {
+            back: grid.clone(),
+            front: grid,
+            neighborhood,
+            edges,
+        }
+    }
+
+    /// Get the current state of the grid.
+    pub fn grid(&self) -> &Grid<T> {
+        &self.front
+    }
+
+    /// Mutably get the current state of the grid.
+    ///
+    /// It's fine to resize it or replace it with a whole new grid;
+    /// the next step will happen on whatever it is then.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Automaton, EdgeMode, Grid, Neighborhood};
+    /// let mut automaton = Automaton::new(Grid::new(3, 3, 0), Neighborhood::Moore, EdgeMode::Clamp);
+    /// automaton.grid_mut().resize(5, 2, 1);
+    /// automaton.step(|cell, _| cell + 1);
+    /// assert_eq!((automaton.grid().width(), automaton.grid().height()), (5, 2));
+    /// assert_eq!(automaton.grid().row(1), Some(&[1, 1, 1, 2, 2][..]));
+    /// ```
+    pub fn grid_mut(&mut self) -> &mut Grid<T> {
+        &mut self.front
+    }
+
+    /// Turn this back into the current state of the grid.
+    pub fn into_grid(self) -> Grid<T> {
+        self.front
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    pub fn edges(&self) -> &EdgeMode<T> {
+        &self.edges
+    }
+
+    /// Step the automaton forward once.
+    ///
+    /// The rule is given each cell's current value and its neighbors' current values,
+    /// and returns the cell's new value.
+    /// The neighbors are in the order of [`ICoord::neighbors4`] or [`ICoord::neighbors8`],
+    /// depending on the neighborhood.
+    pub fn step<F: FnMut(&T, &[&T]) -> T>(&mut self, mut rule: F) {
+        let Self {
+            front,
+            back,
+            neighborhood,
+            edges,
+        } = self;
+        // The front buffer might have been resized through `grid_mut`
+        if back.size() != front.size() {
+            *back = front.clone();
+        }
+        // Only the back buffer gets written to until the swap
+        let (old, edges): (&Grid<T>, &EdgeMode<T>) = (front, edges);
+        let (width, height) = (old.width() as isize, old.height() as isize);
+
+        let mut neighbors = Vec::with_capacity(8);
+        for (pos, new) in back.iter_coords_mut() {
+            let pos = pos.to_icoord();
+            neighbors.clear();
+            let (four, eight);
+            let positions = match neighborhood {
+                Neighborhood::VonNeumann => {
+                    four = pos.neighbors4();
+                    &four[..]
+                }
+                Neighborhood::Moore => {
+                    eight = pos.neighbors8();
+                    &eight[..]
+                }
+            };
+            for &neighbor in positions.iter() {
+                let neighbor = match edges {
+                    _ if old.contains(neighbor) => &old[neighbor],
+                    EdgeMode::Wrap => {
+                        &old[ICoord::new(
+                            neighbor.x.rem_euclid(width),
+                            neighbor.y.rem_euclid(height),
+                        )]
+                    }
+                    EdgeMode::Clamp => {
+                        &old[ICoord::new(
+                            neighbor.x.clamp(0, width - 1),
+                            neighbor.y.clamp(0, height - 1),
+                        )]
+                    }
+                    EdgeMode::Constant(it) => it,
+                };
+                neighbors.push(neighbor);
+            }
+            *new = rule(&old[pos], &neighbors);
+        }
+
+        std::mem::swap(front, back);
+    }
+
+    /// Step the automaton forward `count` times with the same rule.
+    pub fn step_n<F: FnMut(&T, &[&T]) -> T>(&mut self, count: usize, mut rule: F) {
+        for _ in 0..count {
+            self.step(&mut rule);
+        }
+    }
+}
+
+impl Automaton<bool> {
+    /// Step the automaton forward once using a birth/survival rule.
+    /// `true` cells are alive.
+    pub fn step_life(&mut self, rule: &LifeRule) {
+        self.step(|alive, neighbors| rule.apply(*alive, neighbors.iter().filter(|n| ***n).count()));
+    }
+}
+
+/**
+A "life-like" birth/survival rule, like `B3/S23` (Conway's Game of Life).
+
+- A dead cell with a number of live neighbors in the `B` list comes to life.
+- A live cell with a number of live neighbors in the `S` list survives; otherwise it dies.
+
+You can make one by parsing a string. The sections can come in either order
+and are case-insensitive.
+
+```
+# use cogs_gamedev::grids::{LifeRule, RuleParseError};
+// A good rule for caves
+let caves: LifeRule = "B678/S345678".parse().unwrap();
+assert!(caves.apply(false, 7));
+assert!(!caves.apply(false, 5));
+assert!(caves.apply(true, 5));
+assert_eq!(caves.to_string(), "B678/S345678");
+
+assert_eq!("B3/S2 3".parse::<LifeRule>(), Err(RuleParseError::InvalidCount(' ')));
+assert_eq!("B3".parse::<LifeRule>(), Err(RuleParseError::MissingSection('S')));
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LifeRule {
+    /// `birth[n]` is whether a dead cell with `n` live neighbors comes to life.
+    pub birth: [bool; 9],
+    /// `survival[n]` is whether a live cell with `n` live neighbors stays alive.
+    pub survival: [bool; 9],
+}
+
+impl LifeRule {
+    /// Figure out whether a cell is alive next step.
+    pub fn apply(&self, alive: bool, live_neighbors: usize) -> bool {
+        let table = if alive { &self.survival } else { &self.birth };
+        table.get(live_neighbors).copied().unwrap_or(false)
+    }
+}
+
+impl FromStr for LifeRule {
+    type Err = RuleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut birth = None;
+        let mut survival = None;
+        for section in s.split('/') {
+            let mut chars = section.chars();
+            let (slot, name) = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => (&mut birth, 'B'),
+                Some('S') => (&mut survival, 'S'),
+                Some(other) => return Err(RuleParseError::UnknownSection(other)),
+                None => return Err(RuleParseError::EmptySection),
+            };
+            if slot.is_some() {
+                return Err(RuleParseError::DuplicateSection(name));
+            }
+            let mut counts = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(n) if n <= 8 => counts[n as usize] = true,
+                    _ => return Err(RuleParseError::InvalidCount(c)),
+                }
+            }
+            *slot = Some(counts);
+        }
+
+        Ok(Self {
+            birth: birth.ok_or(RuleParseError::MissingSection('B'))?,
+            survival: survival.ok_or(RuleParseError::MissingSection('S'))?,
+        })
+    }
+}
+
+impl Display for LifeRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "B")?;
+        for (n, _) in self.birth.iter().enumerate().filter(|(_, on)| **on) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for (n, _) in self.survival.iter().enumerate().filter(|(_, on)| **on) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+/// Problems that can happen parsing a [`LifeRule`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleParseError {
+    /// There was nothing between two slashes (or at the start or end).
+    EmptySection,
+    /// A section started with something other than `B` or `S`.
+    UnknownSection(char),
+    /// The `B` or `S` section appeared twice.
+    DuplicateSection(char),
+    /// The `B` or `S` section never appeared.
+    MissingSection(char),
+    /// Something other than a digit from 0 to 8 was in a section.
+    InvalidCount(char),
+}
+
+impl Display for RuleParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleParseError::EmptySection => write!(f, "empty section in rule"),
+            RuleParseError::UnknownSection(c) => {
+                write!(f, "unknown section {:?} in rule (expected B or S)", c)
+            }
+            RuleParseError::DuplicateSection(c) => write!(f, "section {} appears twice in rule", c),
+            RuleParseError::MissingSection(c) => write!(f, "section {} is missing from rule", c),
+            RuleParseError::InvalidCount(c) => {
+                write!(f, "invalid neighbor count {:?} in rule (expected 0-8)", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleParseError {}
+
+#[test]
+fn edge_modes() {
+    // Each cell becomes the value of its west neighbor
+    let grid = Grid::from_fn(3, 1, |pos| pos.x);
+    let shift = |_: &usize, neighbors: &[&usize]| *neighbors[3];
+
+    let mut wrap = Automaton::new(grid.clone(), Neighborhood::VonNeumann, EdgeMode::Wrap);
+    wrap.step(shift);
+    assert_eq!(wrap.grid().as_slice(), &[2, 0, 1]);
+
+    let mut clamp = Automaton::new(grid.clone(), Neighborhood::VonNeumann, EdgeMode::Clamp);
+    clamp.step(shift);
+    assert_eq!(clamp.grid().as_slice(), &[0, 0, 1]);
+
+    let mut constant = Automaton::new(grid, Neighborhood::VonNeumann, EdgeMode::Constant(99));
+    constant.step_n(2, shift);
+    assert_eq!(constant.into_grid().as_slice(), &[99, 99, 0]);
+}
@@ -0,0 +1,279 @@
+//! Sparse storage for unbounded grid-based worlds.
+
+use super::{Coord, Grid, ICoord, IRect};
+
+use ahash::AHashMap;
+
+/// A sparse, unbounded grid of values, split up into square chunks.
+///
+/// Chunks are allocated on demand when you write to them, and can be unloaded
+/// whenever you like (perhaps after saving them to disk).
+/// Each chunk is a [`Grid`] of `chunk_size * chunk_size` cells.
+///
+/// Reading from a position in an unloaded chunk returns `None`;
+/// it does *not* allocate anything.
+///
+/// ```
+/// # use cogs_gamedev::grids::{ChunkedGrid, ICoord};
+/// let mut world = ChunkedGrid::new(16, '.');
+///
+/// assert_eq!(world.get(ICoord::new(-100, 5)), None);
+///
+/// world.set(ICoord::new(-100, 5), '#');
+/// assert_eq!(world.get(ICoord::new(-100, 5)), Some(&'#'));
+/// // The rest of the chunk is filled with the fill value
+/// assert_eq!(world.get(ICoord::new(-99, 5)), Some(&'.'));
+/// assert_eq!(world.loaded_chunk_count(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChunkedGrid<T> {
+    chunk_size: usize,
+    fill: T,
+    chunks: AHashMap<ICoord, Grid<T>>,
+}
+
+impl<T: Clone> ChunkedGrid<T> {
+    /// Make a new, empty chunked grid.
+    ///
+    /// Newly allocated chunks have every cell set to a clone of `fill`.
+    ///
+    /// Panics if `chunk_size` is 0.
+    pub fn new(chunk_size: usize, fill: T) -> Self {
+        assert_ne!(chunk_size, 0, "Chunks cannot have a size of 0!");
+        Self {
+            chunk_size,
+            fill,
+            chunks: AHashMap::new(),
+        }
+    }
+
+    /// The width and height of each chunk.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// The value newly allocated cells are set to.
+    pub fn fill(&self) -> &T {
+        &self.fill
+    }
+
+    /// Get the position of the chunk containing this position.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ChunkedGrid, ICoord};
+    /// let world = ChunkedGrid::new(16, 0u8);
+    /// assert_eq!(world.chunk_pos(ICoord::new(15, 16)), ICoord::new(0, 1));
+    /// assert_eq!(world.chunk_pos(ICoord::new(-1, -16)), ICoord::new(-1, -1));
+    /// assert_eq!(world.chunk_pos(ICoord::new(-17, 0)), ICoord::new(-2, 0));
+    /// ```
+    pub fn chunk_pos(&self, pos: ICoord) -> ICoord {
+        let size = self.chunk_size as isize;
+        ICoord::new(pos.x.div_euclid(size), pos.y.div_euclid(size))
+    }
+
+    /// Get the position of this position inside of its chunk.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ChunkedGrid, Coord, ICoord};
+    /// let world = ChunkedGrid::new(16, 0u8);
+    /// assert_eq!(world.local_pos(ICoord::new(15, 16)), Coord::new(15, 0));
+    /// assert_eq!(world.local_pos(ICoord::new(-1, -16)), Coord::new(15, 0));
+    /// ```
+    pub fn local_pos(&self, pos: ICoord) -> Coord {
+        let size = self.chunk_size as isize;
+        Coord::new(
+            pos.x.rem_euclid(size) as usize,
+            pos.y.rem_euclid(size) as usize,
+        )
+    }
+
+    /// Get the area in world space covered by the chunk at the given chunk position.
+    pub fn chunk_bounds(&self, chunk_pos: ICoord) -> IRect {
+        let size = self.chunk_size as isize;
+        IRect::new(
+            chunk_pos.x * size,
+            chunk_pos.y * size,
+            self.chunk_size,
+            self.chunk_size,
+        )
+    }
+
+    /// Get the value at this position.
+    /// Returns `None` if the chunk it's in isn't loaded.
+    pub fn get(&self, pos: ICoord) -> Option<&T> {
+        let chunk = self.chunks.get(&self.chunk_pos(pos))?;
+        chunk.get(self.local_pos(pos))
+    }
+
+    /// Mutably get the value at this position.
+    /// Returns `None` if the chunk it's in isn't loaded.
+    pub fn get_mut(&mut self, pos: ICoord) -> Option<&mut T> {
+        let local = self.local_pos(pos);
+        let chunk = self.chunks.get_mut(&self.chunk_pos(pos))?;
+        chunk.get_mut(local)
+    }
+
+    /// Mutably get the value at this position,
+    /// loading the chunk it's in if it isn't already.
+    pub fn get_or_load_mut(&mut self, pos: ICoord) -> &mut T {
+        let local = self.local_pos(pos);
+        let chunk = self.load_chunk(self.chunk_pos(pos));
+        &mut chunk[local]
+    }
+
+    /// Set the value at this position,
+    /// loading the chunk it's in if it isn't already.
+    ///
+    /// Returns the old value.
+    pub fn set(&mut self, pos: ICoord, value: T) -> T {
+        std::mem::replace(self.get_or_load_mut(pos), value)
+    }
+
+    /// Is the chunk at the given chunk position loaded?
+    pub fn is_loaded(&self, chunk_pos: ICoord) -> bool {
+        self.chunks.contains_key(&chunk_pos)
+    }
+
+    /// How many chunks are currently loaded?
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Get the chunk at the given chunk position, if it's loaded.
+    pub fn chunk(&self, chunk_pos: ICoord) -> Option<&Grid<T>> {
+        self.chunks.get(&chunk_pos)
+    }
+
+    /// Mutably get the chunk at the given chunk position, if it's loaded.
+    pub fn chunk_mut(&mut self, chunk_pos: ICoord) -> Option<&mut Grid<T>> {
+        self.chunks.get_mut(&chunk_pos)
+    }
+
+    /// Get the chunk at the given chunk position,
+    /// allocating it and filling it with the fill value if it isn't loaded.
+    pub fn load_chunk(&mut self, chunk_pos: ICoord) -> &mut Grid<T> {
+        let (size, fill) = (self.chunk_size, &self.fill);
+        self.chunks
+            .entry(chunk_pos)
+            .or_insert_with(|| Grid::new(size, size, fill.clone()))
+    }
+
+    /// Put a chunk into the grid at the given chunk position,
+    /// perhaps after loading it from disk.
+    ///
+    /// Returns the chunk that was previously there, if any.
+    ///
+    /// Panics if the chunk isn't `chunk_size * chunk_size`.
+    pub fn insert_chunk(&mut self, chunk_pos: ICoord, chunk: Grid<T>) -> Option<Grid<T>> {
+        assert_eq!(
+            chunk.size(),
+            Coord::new(self.chunk_size, self.chunk_size),
+            "Chunk is the wrong size!"
+        );
+        self.chunks.insert(chunk_pos, chunk)
+    }
+
+    /// Unload the chunk at the given chunk position, returning it if it was loaded.
+    pub fn unload_chunk(&mut self, chunk_pos: ICoord) -> Option<Grid<T>> {
+        self.chunks.remove(&chunk_pos)
+    }
+
+    /// Unload every chunk for which the predicate returns `false`.
+    ///
+    /// This is handy for unloading everything far away from the player.
+    pub fn retain_chunks<F: FnMut(ICoord, &Grid<T>) -> bool>(&mut self, mut keep: F) {
+        self.chunks.retain(|pos, chunk| keep(*pos, chunk));
+    }
+
+    /// Unload every chunk.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Iterate over all the loaded chunks and their chunk positions, in no particular order.
+    pub fn chunks(&self) -> impl Iterator<Item = (ICoord, &Grid<T>)> + '_ {
+        self.chunks.iter().map(|(pos, chunk)| (*pos, chunk))
+    }
+
+    /// Mutably iterate over all the loaded chunks and their chunk positions, in no particular order.
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = (ICoord, &mut Grid<T>)> + '_ {
+        self.chunks.iter_mut().map(|(pos, chunk)| (*pos, chunk))
+    }
+
+    /// Iterate over every loaded cell and its world position.
+    ///
+    /// Chunks are visited in no particular order, but each chunk is visited in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (ICoord, &T)> + '_ {
+        self.chunks().flat_map(move |(chunk_pos, chunk)| {
+            let corner = self.chunk_bounds(chunk_pos);
+            let corner = ICoord::new(corner.left, corner.top);
+            chunk
+                .iter_coords()
+                .map(move |(local, val)| (corner + local.to_icoord(), val))
+        })
+    }
+
+    /// Iterate over every position in the given area, along with the value there
+    /// (or `None` if it's in an unloaded chunk).
+    ///
+    /// Positions are visited in reading order.
+    pub fn region(&self, area: IRect) -> impl Iterator<Item = (ICoord, Option<&T>)> + '_ {
+        area.contained_coords().map(move |pos| (pos, self.get(pos)))
+    }
+
+    /// Copy the given area out into a [`Grid`].
+    ///
+    /// Cells in unloaded chunks are filled with the fill value.
+    /// The top-left corner of the area is `(0, 0)` in the output.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ChunkedGrid, ICoord, IRect};
+    /// let mut world = ChunkedGrid::new(4, 0);
+    /// world.set(ICoord::new(-1, -1), 1);
+    /// world.set(ICoord::new(0, 0), 2);
+    ///
+    /// let region = world.read_region(IRect::new(-1, -1, 3, 2));
+    /// assert_eq!(region.as_slice(), &[1, 0, 0, 0, 2, 0]);
+    /// ```
+    pub fn read_region(&self, area: IRect) -> Grid<T> {
+        let corner = ICoord::new(area.left, area.top);
+        Grid::from_fn(area.width, area.height, |local| {
+            self.get(corner + local.to_icoord())
+                .unwrap_or(&self.fill)
+                .clone()
+        })
+    }
+
+    /// Copy a [`Grid`] into the world, with its top-left corner at the given position.
+    ///
+    /// Any chunks it covers are loaded.
+    pub fn write_region(&mut self, corner: ICoord, region: &Grid<T>) {
+        for (local, val) in region.iter_coords() {
+            self.set(corner + local.to_icoord(), val.clone());
+        }
+    }
+}
+
+#[test]
+fn chunk_boundaries() {
+    let mut world = ChunkedGrid::new(3, 0);
+    let area = IRect::new(-4, -4, 8, 8);
+    for (idx, pos) in area.contained_coords().enumerate() {
+        world.set(pos, idx);
+    }
+    // -4..4 crosses chunks -2, -1, 0, 1 on each axis
+    assert_eq!(world.loaded_chunk_count(), 16);
+    for (idx, (_, val)) in world.region(area).enumerate() {
+        assert_eq!(val, Some(&idx));
+    }
+    assert_eq!(world.iter().count(), 16 * 9);
+
+    world.retain_chunks(|pos, _| pos.x >= 0);
+    assert_eq!(world.loaded_chunk_count(), 8);
+    assert_eq!(world.get(ICoord::new(-1, 0)), None);
+    assert_eq!(world.get(ICoord::new(0, 0)), Some(&36));
+
+    let unloaded = world.unload_chunk(ICoord::new(0, 0)).unwrap();
+    assert_eq!(unloaded[ICoord::new(0, 0)], 36);
+    assert!(!world.is_loaded(ICoord::new(0, 0)));
+}
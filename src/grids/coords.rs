@@ -1,6 +1,6 @@
 //! Integer-based coordinates.
 
-use super::{Direction4, Direction8};
+use super::{Direction4, Direction8, LineIter, SupercoverIter};
 
 use itertools::Itertools;
 #[cfg(feature = "serde")]
@@ -339,6 +339,50 @@ impl ICoord {
             self + Direction8::NorthWest,
         ]
     }
+
+    /// Get an iterator over the cells on a [Bresenham line](https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm)
+    /// from this to the other coordinate, including both ends.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::ICoord;
+    /// assert_eq!(
+    ///     ICoord::new(0, 0).line_to(ICoord::new(5, 2)).collect::<Vec<_>>(),
+    ///     vec![
+    ///         ICoord::new(0, 0),
+    ///         ICoord::new(1, 0),
+    ///         ICoord::new(2, 1),
+    ///         ICoord::new(3, 1),
+    ///         ICoord::new(4, 2),
+    ///         ICoord::new(5, 2),
+    ///     ]
+    /// );
+    /// ```
+    pub fn line_to(self, other: ICoord) -> LineIter {
+        LineIter::new(self, other)
+    }
+
+    /// Get an iterator over every cell touched by the line from the center of this cell
+    /// to the center of the other one, including both ends.
+    ///
+    /// Unlike [`ICoord::line_to`], this never skips diagonally past a corner.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::ICoord;
+    /// assert_eq!(
+    ///     ICoord::new(0, 0).supercover_line_to(ICoord::new(4, 1)).collect::<Vec<_>>(),
+    ///     vec![
+    ///         ICoord::new(0, 0),
+    ///         ICoord::new(1, 0),
+    ///         ICoord::new(2, 0),
+    ///         ICoord::new(2, 1),
+    ///         ICoord::new(3, 1),
+    ///         ICoord::new(4, 1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn supercover_line_to(self, other: ICoord) -> SupercoverIter {
+        SupercoverIter::new(self, other)
+    }
 }
 
 impl Add for ICoord {
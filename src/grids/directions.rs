@@ -79,7 +79,7 @@ impl Direction4 {
     /// This uses trigonometric + graphical standard, where:
     /// - 0 radians is to the right
     /// - Positive radians increment *clockwise*. NOTE: this is opposite from normal trig,
-    /// but makes sense in computer graphics where +Y is downwards.
+    ///   but makes sense in computer graphics where +Y is downwards.
     ///
     /// If you need it in degrees just call `.to_degrees` on the result.
     ///
@@ -119,6 +119,28 @@ impl Direction4 {
         ICoord { x, y }
     }
 
+    /// Get the direction a step with these deltas would go in.
+    ///
+    /// Returns `None` if the deltas aren't exactly one step in one of the directions.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Direction4;
+    /// # use cogs_gamedev::grids::ICoord;
+    /// use Direction4::*;
+    ///
+    /// assert_eq!(Direction4::from_deltas(ICoord::new(0, 1)), Some(South));
+    /// assert_eq!(Direction4::from_deltas(ICoord::new(1, 1)), None);
+    /// for dir in Direction4::DIRECTIONS.iter() {
+    ///     assert_eq!(Direction4::from_deltas(dir.deltas()), Some(*dir));
+    /// }
+    /// ```
+    pub fn from_deltas(deltas: ICoord) -> Option<Self> {
+        Self::DIRECTIONS
+            .iter()
+            .find(|dir| dir.deltas() == deltas)
+            .copied()
+    }
+
     /// See if this direction points horizontally (ie, is `East` or `West`).
     ///
     /// ```
@@ -232,7 +254,7 @@ impl Direction8 {
     /// This uses trigonometric + graphical standard, where:
     /// - 0 radians is to the right
     /// - Positive radians increment *clockwise*. NOTE: this is opposite from normal trig,
-    /// but makes sense in computer graphics where +Y is downwards.
+    ///   but makes sense in computer graphics where +Y is downwards.
     ///
     /// If you need it in degrees just call `.to_degrees` on the result.
     ///
@@ -279,6 +301,29 @@ impl Direction8 {
         };
         ICoord { x, y }
     }
+
+    /// Get the direction a step with these deltas would go in.
+    ///
+    /// Returns `None` if the deltas aren't exactly one step in one of the directions.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Direction8;
+    /// # use cogs_gamedev::grids::ICoord;
+    /// use Direction8::*;
+    ///
+    /// assert_eq!(Direction8::from_deltas(ICoord::new(0, 1)), Some(South));
+    /// assert_eq!(Direction8::from_deltas(ICoord::new(1, 1)), Some(SouthEast));
+    /// assert_eq!(Direction8::from_deltas(ICoord::new(2, 0)), None);
+    /// for dir in Direction8::DIRECTIONS.iter() {
+    ///     assert_eq!(Direction8::from_deltas(dir.deltas()), Some(*dir));
+    /// }
+    /// ```
+    pub fn from_deltas(deltas: ICoord) -> Option<Self> {
+        Self::DIRECTIONS
+            .iter()
+            .find(|dir| dir.deltas() == deltas)
+            .copied()
+    }
 }
 
 /// 2-way rotations: clockwise or counterclockwise.
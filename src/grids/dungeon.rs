@@ -0,0 +1,380 @@
+//! Room-and-corridor dungeon generation, the way every roguelike starts.
+//!
+//! The map is recursively split up with [binary space partitioning](http://www.roguebasin.com/index.php/Basic_BSP_Dungeon_generation),
+//! a room is put in each of the leaves, and then sibling parts of the tree are connected with corridors.
+//! Because every split gets connected, every room is reachable from every other room.
+//!
+//! Everything random comes from the `Rng` you pass in, so the same seed gets you the same dungeon.
+//!
+//! ```
+//! # use cogs_gamedev::grids::dungeon::{self, DungeonConfig, Tile};
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let config = DungeonConfig {
+//!     width: 60,
+//!     height: 40,
+//!     ..Default::default()
+//! };
+//! let a = dungeon::generate(&config, &mut StdRng::seed_from_u64(1234));
+//! let b = dungeon::generate(&config, &mut StdRng::seed_from_u64(1234));
+//! assert_eq!(a, b);
+//!
+//! for room in a.rooms.iter() {
+//!     for pos in room.bounds.contained_coords() {
+//!         assert_eq!(a.tiles[pos], Tile::Floor);
+//!     }
+//!     for door in room.doors.iter() {
+//!         assert_eq!(a.tiles[door.pos], Tile::Door);
+//!         assert!(room.bounds.contains(door.pos - door.side.deltas()));
+//!     }
+//! }
+//! ```
+
+use super::{
+    pathfinding::{self, Heuristic, Movement},
+    Direction4, Grid, ICoord, IRect,
+};
+
+use rand::Rng;
+
+/// One cell of a generated dungeon.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Tile {
+    Wall,
+    /// The inside of a room.
+    Floor,
+    /// A corridor between rooms.
+    Corridor,
+    /// Where a corridor meets a room.
+    Door,
+}
+
+/// How corridors are routed between rooms.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CorridorStyle {
+    /// Go straight horizontally then vertically, or the other way around (picked randomly).
+    LShaped,
+    /// Use A* to find a route, preferring to reuse corridors and rooms that are already dug out.
+    /// This makes fewer redundant parallel corridors.
+    Pathfinding,
+}
+
+/// Settings for [`generate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DungeonConfig {
+    /// Width of the whole map.
+    pub width: usize,
+    /// Height of the whole map.
+    pub height: usize,
+    /// BSP leaves are never split into parts smaller than this on either axis.
+    pub min_leaf_size: usize,
+    /// Leaves bigger than this on either axis are always split.
+    pub max_leaf_size: usize,
+    /// Rooms are at least this big on each axis (unless their leaf is too small to fit it).
+    pub min_room_size: usize,
+    /// How many cells of wall to leave between the edge of a leaf and its room.
+    pub room_padding: usize,
+    pub corridors: CorridorStyle,
+}
+
+impl Default for DungeonConfig {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            height: 50,
+            min_leaf_size: 8,
+            max_leaf_size: 20,
+            min_room_size: 4,
+            room_padding: 1,
+            corridors: CorridorStyle::LShaped,
+        }
+    }
+}
+
+/// A generated dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dungeon {
+    /// The map. The top-left corner is `(0, 0)`.
+    pub tiles: Grid<Tile>,
+    pub rooms: Vec<Room>,
+}
+
+/// One room in a generated dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Room {
+    /// The floor of the room (not including the walls around it).
+    pub bounds: IRect,
+    pub doors: Vec<Door>,
+}
+
+/// A door leading out of a room.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Door {
+    /// Where the door is. This is just outside the room's bounds, where the wall would be.
+    pub pos: ICoord,
+    /// Which side of the room the door is on.
+    pub side: Direction4,
+}
+
+/// Generate a dungeon.
+///
+/// The outermost ring of cells is always wall.
+pub fn generate<R: Rng + ?Sized>(config: &DungeonConfig, rng: &mut R) -> Dungeon {
+    let mut tiles = Grid::new(config.width, config.height, Tile::Wall);
+    let mut rooms = Vec::new();
+
+    let inside = tiles.bounds().shrink(1);
+    if inside.area() == 0 {
+        return Dungeon { tiles, rooms };
+    }
+
+    let mut leaves = Vec::new();
+    let tree = split(inside, config, rng, &mut leaves);
+
+    for leaf in leaves.iter() {
+        let room = place_room(*leaf, config, rng);
+        tiles.fill_rect(room, Tile::Floor);
+        rooms.push(Room {
+            bounds: room,
+            doors: Vec::new(),
+        });
+    }
+
+    let mut dungeon = Dungeon { tiles, rooms };
+    connect(&tree, inside, config, rng, &mut dungeon);
+    dungeon
+}
+
+/// A BSP tree; the leaves are indices into the list of rooms.
+enum Node {
+    Leaf(usize),
+    Split(Box<Node>, Box<Node>),
+}
+
+impl Node {
+    fn rooms(&self, out: &mut Vec<usize>) {
+        match self {
+            Node::Leaf(idx) => out.push(*idx),
+            Node::Split(a, b) => {
+                a.rooms(out);
+                b.rooms(out);
+            }
+        }
+    }
+}
+
+fn split<R: Rng + ?Sized>(
+    area: IRect,
+    config: &DungeonConfig,
+    rng: &mut R,
+    leaves: &mut Vec<IRect>,
+) -> Node {
+    let min = config.min_leaf_size.max(1);
+    let can_split_h = area.width >= min * 2;
+    let can_split_v = area.height >= min * 2;
+    let too_big = area.width > config.max_leaf_size || area.height > config.max_leaf_size;
+    // Sometimes stop early even if we could keep going, for variety
+    let stop = !too_big && rng.gen_bool(0.25);
+
+    let horizontal = match (can_split_h, can_split_v) {
+        _ if stop => None,
+        (false, false) => None,
+        (true, false) => Some(true),
+        (false, true) => Some(false),
+        // Prefer cutting across the long side so things don't get too skinny
+        (true, true) if area.width as f64 > area.height as f64 * 1.25 => Some(true),
+        (true, true) if area.height as f64 > area.width as f64 * 1.25 => Some(false),
+        (true, true) => Some(rng.gen()),
+    };
+
+    match horizontal {
+        None => {
+            leaves.push(area);
+            Node::Leaf(leaves.len() - 1)
+        }
+        Some(horizontal) => {
+            let len = if horizontal { area.width } else { area.height };
+            let offset = rng.gen_range(min..=len - min);
+            let (a, b) = if horizontal {
+                area.split_horizontal(offset)
+            } else {
+                area.split_vertical(offset)
+            };
+            Node::Split(
+                Box::new(split(a, config, rng, leaves)),
+                Box::new(split(b, config, rng, leaves)),
+            )
+        }
+    }
+}
+
+fn place_room<R: Rng + ?Sized>(leaf: IRect, config: &DungeonConfig, rng: &mut R) -> IRect {
+    let mut space = leaf.shrink(config.room_padding);
+    if space.area() == 0 {
+        // Padding ate the whole thing; just use the middle of the leaf
+        space = IRect::new(leaf.center().x, leaf.center().y, 1, 1);
+    }
+    let width = rng.gen_range(config.min_room_size.clamp(1, space.width)..=space.width);
+    let height = rng.gen_range(config.min_room_size.clamp(1, space.height)..=space.height);
+    let left = space.left + rng.gen_range(0..=space.width - width) as isize;
+    let top = space.top + rng.gen_range(0..=space.height - height) as isize;
+    IRect::new(left, top, width, height)
+}
+
+fn connect<R: Rng + ?Sized>(
+    node: &Node,
+    inside: IRect,
+    config: &DungeonConfig,
+    rng: &mut R,
+    dungeon: &mut Dungeon,
+) {
+    let (a, b) = match node {
+        Node::Leaf(_) => return,
+        Node::Split(a, b) => (a, b),
+    };
+    connect(a, inside, config, rng, dungeon);
+    connect(b, inside, config, rng, dungeon);
+
+    // Connect the closest pair of rooms across the split
+    let (mut a_rooms, mut b_rooms) = (Vec::new(), Vec::new());
+    a.rooms(&mut a_rooms);
+    b.rooms(&mut b_rooms);
+    let mut best = None;
+    for &ra in a_rooms.iter() {
+        for &rb in b_rooms.iter() {
+            let delta = dungeon.rooms[ra].bounds.center() - dungeon.rooms[rb].bounds.center();
+            let dist = delta.x.abs() + delta.y.abs();
+            let closer = match best {
+                Some((_, _, best_dist)) => dist < best_dist,
+                None => true,
+            };
+            if closer {
+                best = Some((ra, rb, dist));
+            }
+        }
+    }
+    let (ra, rb, _) = match best {
+        Some(it) => it,
+        None => return,
+    };
+
+    let start = random_point(dungeon.rooms[ra].bounds, rng);
+    let end = random_point(dungeon.rooms[rb].bounds, rng);
+    let path = match config.corridors {
+        CorridorStyle::LShaped => {
+            let corner = if rng.gen() {
+                ICoord::new(end.x, start.y)
+            } else {
+                ICoord::new(start.x, end.y)
+            };
+            let mut path = start.line_to(corner).collect::<Vec<_>>();
+            path.extend(corner.line_to(end).skip(1));
+            path
+        }
+        CorridorStyle::Pathfinding => {
+            let tiles = &dungeon.tiles;
+            pathfinding::astar(start, end, Movement::Four, Heuristic::Manhattan, |pos| {
+                if !inside.contains(pos) {
+                    return None;
+                }
+                Some(match tiles[pos] {
+                    Tile::Wall => 4.0,
+                    _ => 1.0,
+                })
+            })
+            .map(|(path, _)| path)
+            .unwrap_or_default()
+        }
+    };
+
+    for pos in path.iter() {
+        if dungeon.tiles[*pos] == Tile::Wall {
+            dungeon.tiles[*pos] = Tile::Corridor;
+        }
+    }
+    add_door(dungeon, ra, path.iter());
+    add_door(dungeon, rb, path.iter().rev());
+}
+
+/// Walk along the path until it leaves the room, and put a door there.
+fn add_door<'a, I: Iterator<Item = &'a ICoord>>(dungeon: &mut Dungeon, room_idx: usize, path: I) {
+    let room = dungeon.rooms[room_idx].bounds;
+    let mut prev = None;
+    for pos in path {
+        if !room.contains(*pos) {
+            if let Some(side) = prev.and_then(|prev| Direction4::from_deltas(*pos - prev)) {
+                let in_other_room = dungeon.rooms.iter().any(|r| r.bounds.contains(*pos));
+                if !in_other_room {
+                    dungeon.tiles[*pos] = Tile::Door;
+                    let door = Door { pos: *pos, side };
+                    if !dungeon.rooms[room_idx].doors.contains(&door) {
+                        dungeon.rooms[room_idx].doors.push(door);
+                    }
+                }
+            }
+            return;
+        }
+        prev = Some(*pos);
+    }
+}
+
+fn random_point<R: Rng + ?Sized>(rect: IRect, rng: &mut R) -> ICoord {
+    ICoord::new(
+        rng.gen_range(rect.left..=rect.right()),
+        rng.gen_range(rect.top..=rect.bottom()),
+    )
+}
+
+#[test]
+fn everything_connected() {
+    use ahash::AHashSet;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    for seed in 0..20 {
+        for &corridors in &[CorridorStyle::LShaped, CorridorStyle::Pathfinding] {
+            let config = DungeonConfig {
+                corridors,
+                ..Default::default()
+            };
+            let dungeon = generate(&config, &mut StdRng::seed_from_u64(seed));
+            assert!(dungeon.rooms.len() > 1);
+
+            // Flood fill from the first room, and it should get everywhere that isn't a wall
+            let start = dungeon.rooms[0].bounds.center();
+            let mut reached = AHashSet::new();
+            let mut frontier = vec![start];
+            while let Some(pos) = frontier.pop() {
+                if dungeon.tiles.get(pos).is_some_and(|t| *t != Tile::Wall) && reached.insert(pos) {
+                    frontier.extend(pos.neighbors4().iter().copied());
+                }
+            }
+            for (pos, tile) in dungeon.tiles.iter_coords() {
+                if *tile != Tile::Wall {
+                    assert!(
+                        reached.contains(&pos.to_icoord()),
+                        "seed {} {:?} {}",
+                        seed,
+                        corridors,
+                        pos
+                    );
+                }
+            }
+            for room in dungeon.rooms.iter() {
+                assert!(!room.doors.is_empty() || dungeon.rooms.len() == 1);
+            }
+
+            // The border is all wall
+            for pos in dungeon.tiles.bounds().contained_coords() {
+                if !dungeon.tiles.bounds().shrink(1).contains(pos) {
+                    assert_eq!(dungeon.tiles[pos], Tile::Wall);
+                }
+            }
+            // Rooms don't overlap
+            for (i, a) in dungeon.rooms.iter().enumerate() {
+                for b in dungeon.rooms[i + 1..].iter() {
+                    assert!(!a.bounds.intersects(b.bounds));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! Dijkstra maps, AKA flow fields, for moving lots of things towards (or away from) goals.
+
+use super::{
+    pathfinding::{Frontier, Movement},
+    Direction4, Direction8, Grid, ICoord, IRect,
+};
+
+use ahash::AHashSet;
+
+use std::collections::BinaryHeap;
+
+/**
+A "Dijkstra map" over a rectangular region.
+
+It stores, for every cell, how far away the nearest goal is,
+and which direction to step in to get closer to it.
+Once it's been computed, any number of agents can walk downhill towards the goals
+in O(1) per step, without having to pathfind individually.
+
+Each goal has a weight, which is the distance it starts at.
+Goals with lower weights are more attractive; with a weight of `-5.0`, an agent
+would walk 5 cells further to reach that goal instead of one with a weight of `0.0`.
+
+See [this article](http://www.roguebasin.com/index.php/The_Incredible_Power_of_Dijkstra_Maps)
+for all the fun things you can do with these.
+
+```
+# use cogs_gamedev::grids::{FlowField, ICoord, IRect, Direction8};
+# use cogs_gamedev::grids::pathfinding::{CornerCutting, Movement};
+let mut field = FlowField::new(
+    IRect::new(0, 0, 10, 10),
+    Movement::Eight(CornerCutting::Never),
+    |_| Some(1.0),
+);
+field.add_goal(ICoord::new(9, 9), 0.0);
+field.recompute();
+
+assert_eq!(field.direction(ICoord::new(0, 0)), Some(Direction8::SouthEast));
+assert_eq!(field.direction(ICoord::new(9, 0)), Some(Direction8::South));
+assert_eq!(field.direction(ICoord::new(9, 9)), None);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct FlowField {
+    bounds: IRect,
+    movement: Movement,
+    /// Cost to enter each cell, or `None` if it's impassable
+    costs: Grid<Option<f64>>,
+    /// Starting weight of each goal
+    goals: Grid<Option<f64>>,
+    /// Distance from each cell to the nearest goal; infinite if unreachable
+    distances: Grid<f64>,
+    /// Which way to step to get to the nearest goal
+    directions: Grid<Option<Direction8>>,
+}
+
+impl FlowField {
+    /// Make a new flow field covering the given area.
+    ///
+    /// `cost` is called once for every cell in the area, and should return the cost to move into
+    /// the cell, or `None` if it can't be entered. Cells outside the area can never be entered.
+    ///
+    /// The new field has no goals; add some and then call [`FlowField::recompute`].
+    pub fn new<C: FnMut(ICoord) -> Option<f64>>(
+        bounds: IRect,
+        movement: Movement,
+        mut cost: C,
+    ) -> Self {
+        let corner = ICoord::new(bounds.left, bounds.top);
+        Self {
+            bounds,
+            movement,
+            costs: Grid::from_fn(bounds.width, bounds.height, |local| {
+                cost(corner + local.to_icoord())
+            }),
+            goals: Grid::new(bounds.width, bounds.height, None),
+            distances: Grid::new(bounds.width, bounds.height, f64::INFINITY),
+            directions: Grid::new(bounds.width, bounds.height, None),
+        }
+    }
+
+    /// The area this field covers.
+    pub fn bounds(&self) -> IRect {
+        self.bounds
+    }
+
+    /// How things are allowed to move around this field.
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    /// Add a goal with the given weight, or change the weight of an existing goal.
+    /// Goals outside the bounds are ignored.
+    ///
+    /// This doesn't update the field; call [`FlowField::recompute`] once you're done adding goals.
+    pub fn add_goal(&mut self, pos: ICoord, weight: f64) {
+        let local = self.local(pos);
+        if let Some(goal) = self.goals.get_mut(local) {
+            *goal = Some(weight);
+        }
+    }
+
+    /// Remove the goal at the given position, returning its weight if there was one.
+    ///
+    /// This doesn't update the field; call [`FlowField::recompute`] once you're done removing goals.
+    pub fn remove_goal(&mut self, pos: ICoord) -> Option<f64> {
+        let local = self.local(pos);
+        self.goals.get_mut(local)?.take()
+    }
+
+    /// Remove all the goals.
+    pub fn clear_goals(&mut self) {
+        self.goals.fill(None);
+    }
+
+    /// Iterate over all the goals and their weights.
+    pub fn goals(&self) -> impl Iterator<Item = (ICoord, f64)> + '_ {
+        let corner = self.corner();
+        self.goals
+            .iter_coords()
+            .filter_map(move |(local, weight)| weight.map(|w| (corner + local.to_icoord(), w)))
+    }
+
+    /// Get the cost to move into the given cell,
+    /// or `None` if it's impassable or out of bounds.
+    pub fn cost(&self, pos: ICoord) -> Option<f64> {
+        self.costs.get(self.local(pos)).copied().flatten()
+    }
+
+    /// Recompute the entire field from scratch.
+    pub fn recompute(&mut self) {
+        self.distances.fill(f64::INFINITY);
+        self.directions.fill(None);
+
+        let mut queue = BinaryHeap::new();
+        let corner = self.corner();
+        for (local, weight) in self.goals.iter_coords() {
+            if let (Some(weight), Some(_)) = (weight, self.costs[local]) {
+                self.distances[local] = *weight;
+                queue.push(Frontier {
+                    estimate: *weight,
+                    cost: *weight,
+                    pos: corner + local.to_icoord(),
+                });
+            }
+        }
+        self.propagate(queue);
+    }
+
+    /// Change the cost of moving into one cell, and update the field to match.
+    ///
+    /// Pass `None` to make the cell impassable.
+    /// Only the part of the field affected by the change is recomputed.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{FlowField, ICoord, IRect};
+    /// # use cogs_gamedev::grids::pathfinding::Movement;
+    /// let mut field = FlowField::new(IRect::new(0, 0, 5, 1), Movement::Four, |_| Some(1.0));
+    /// field.add_goal(ICoord::new(0, 0), 0.0);
+    /// field.recompute();
+    /// assert_eq!(field.distance(ICoord::new(4, 0)), Some(4.0));
+    ///
+    /// field.set_cost(ICoord::new(2, 0), None);
+    /// assert_eq!(field.distance(ICoord::new(4, 0)), None);
+    ///
+    /// field.set_cost(ICoord::new(2, 0), Some(3.0));
+    /// assert_eq!(field.distance(ICoord::new(4, 0)), Some(6.0));
+    /// ```
+    pub fn set_cost(&mut self, pos: ICoord, cost: Option<f64>) {
+        let local = self.local(pos);
+        match self.costs.get_mut(local) {
+            Some(old) if *old != cost => *old = cost,
+            _ => return,
+        }
+
+        // Everything next to the changed cell might have changed
+        // (stepping into it costs something different, and diagonal moves around it
+        // might have become allowed or disallowed).
+        // So might everything whose path to a goal goes through there.
+        // This only touches the invalidated cells, not the whole field.
+        let mut invalid = AHashSet::new();
+        let mut stack = Vec::new();
+        for dir in Direction8::DIRECTIONS.iter() {
+            stack.push(pos + *dir);
+        }
+        stack.push(pos);
+        while let Some(cursor) = stack.pop() {
+            if self.costs.get(self.local(cursor)).is_none() || !invalid.insert(cursor) {
+                continue;
+            }
+            for dir in Direction8::DIRECTIONS.iter() {
+                let neighbor = cursor + *dir;
+                if self.direction(neighbor) == Some(dir.flip()) {
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        for &cell in invalid.iter() {
+            let local = self.local(cell);
+            self.distances[local] = f64::INFINITY;
+            self.directions[local] = None;
+        }
+
+        // Seed each invalidated cell with the best it can do from its valid neighbors
+        let mut queue = BinaryHeap::new();
+        for &cell in invalid.iter() {
+            let local = self.local(cell);
+            if self.costs[local].is_none() {
+                continue;
+            }
+            let mut best = (self.goals[local].unwrap_or(f64::INFINITY), None);
+            for &dir in self.movement.directions() {
+                let neighbor = cell + dir;
+                let neighbor_dist = self.distance_raw(neighbor);
+                if neighbor_dist.is_infinite() {
+                    continue;
+                }
+                if let Some(step) = self.step_cost(cell, dir) {
+                    if step + neighbor_dist < best.0 {
+                        best = (step + neighbor_dist, Some(dir));
+                    }
+                }
+            }
+            if best.0.is_finite() {
+                self.distances[local] = best.0;
+                self.directions[local] = best.1;
+                queue.push(Frontier {
+                    estimate: best.0,
+                    cost: best.0,
+                    pos: cell,
+                });
+            }
+        }
+        self.propagate(queue);
+    }
+
+    /**
+    Make a "fleeing map" from this field.
+
+    Agents following a fleeing map run away from the goals, but intelligently:
+    they'll head for open areas instead of backing themselves into corners.
+
+    Every reachable cell's distance is multiplied by `coefficient` and made into a goal,
+    and then the field is rescanned. `coefficient` should be negative;
+    around `-1.2` is a good place to start. Bigger magnitudes make agents more
+    willing to run past the thing they're fleeing to reach safety.
+
+    ```
+    # use cogs_gamedev::grids::{FlowField, ICoord, IRect, Direction8};
+    # use cogs_gamedev::grids::pathfinding::Movement;
+    let mut field = FlowField::new(IRect::new(0, 0, 10, 1), Movement::Four, |_| Some(1.0));
+    field.add_goal(ICoord::new(3, 0), 0.0);
+    field.recompute();
+    assert_eq!(field.direction(ICoord::new(5, 0)), Some(Direction8::West));
+
+    let flee = field.flee(-1.2);
+    assert_eq!(flee.direction(ICoord::new(5, 0)), Some(Direction8::East));
+    ```
+    */
+    pub fn flee(&self, coefficient: f64) -> FlowField {
+        let mut out = self.clone();
+        out.clear_goals();
+        for (local, dist) in self.distances.iter_coords() {
+            if dist.is_finite() {
+                out.goals[local] = Some(dist * coefficient);
+            }
+        }
+        out.recompute();
+        out
+    }
+
+    /// Get the distance from this cell to the nearest goal (including its weight).
+    ///
+    /// Returns `None` if the cell is out of bounds, impassable, or can't reach any goal.
+    pub fn distance(&self, pos: ICoord) -> Option<f64> {
+        Some(self.distance_raw(pos)).filter(|dist| dist.is_finite())
+    }
+
+    /// Get the direction to step in from this cell to get closer to the nearest goal.
+    ///
+    /// Returns `None` if the cell is out of bounds, impassable, can't reach any goal,
+    /// or there's nowhere better to go (ie, it's on a goal).
+    pub fn direction(&self, pos: ICoord) -> Option<Direction8> {
+        self.directions.get(self.local(pos)).copied().flatten()
+    }
+
+    /// Get the direction to step in from this cell as a [`Direction4`].
+    ///
+    /// This is only useful with [`Movement::Four`]; with [`Movement::Eight`]
+    /// this returns `None` whenever the best step is diagonal.
+    pub fn direction4(&self, pos: ICoord) -> Option<Direction4> {
+        Direction4::from_deltas(self.direction(pos)?.deltas())
+    }
+
+    /// Get the cell to step into from this cell to get closer to the nearest goal.
+    ///
+    /// Returns `None` in the same cases as [`FlowField::direction`].
+    pub fn next_step(&self, pos: ICoord) -> Option<ICoord> {
+        self.direction(pos).map(|dir| pos + dir)
+    }
+
+    /// Follow the field from `start` until it gets to a goal, or a cell with nowhere better to go.
+    ///
+    /// The path includes `start`.
+    pub fn path_from(&self, start: ICoord) -> Vec<ICoord> {
+        let mut path = vec![start];
+        let mut cursor = start;
+        while let Some(next) = self.next_step(cursor) {
+            path.push(next);
+            cursor = next;
+        }
+        path
+    }
+
+    /// Run Dijkstra outwards from everything in the queue.
+    fn propagate(&mut self, mut queue: BinaryHeap<Frontier>) {
+        while let Some(Frontier { cost, pos, .. }) = queue.pop() {
+            if cost > self.distance_raw(pos) {
+                // stale
+                continue;
+            }
+            for &dir in self.movement.directions() {
+                let neighbor = pos + dir;
+                let local = self.local(neighbor);
+                // Flip it because we want the cost of the neighbor stepping into us
+                let step = match self.costs.get(local) {
+                    Some(Some(_)) => self.step_cost(neighbor, dir.flip()),
+                    _ => None,
+                };
+                if let Some(step) = step {
+                    let new_dist = cost + step;
+                    if new_dist < self.distances[local] {
+                        self.distances[local] = new_dist;
+                        self.directions[local] = Some(dir.flip());
+                        queue.push(Frontier {
+                            estimate: new_dist,
+                            cost: new_dist,
+                            pos: neighbor,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    fn step_cost(&self, pos: ICoord, dir: Direction8) -> Option<f64> {
+        let costs = &self.costs;
+        let corner = self.corner();
+        self.movement.step_cost(pos, dir, &mut |cell: ICoord| {
+            costs.get(cell - corner).copied().flatten()
+        })
+    }
+
+    fn distance_raw(&self, pos: ICoord) -> f64 {
+        self.distances
+            .get(self.local(pos))
+            .copied()
+            .unwrap_or(f64::INFINITY)
+    }
+
+    fn corner(&self) -> ICoord {
+        ICoord::new(self.bounds.left, self.bounds.top)
+    }
+
+    fn local(&self, pos: ICoord) -> ICoord {
+        pos - self.corner()
+    }
+}
+
+#[test]
+fn incremental_matches_full() {
+    let bounds = IRect::new(-3, -2, 12, 9);
+    let movement = Movement::Eight(super::pathfinding::CornerCutting::IfOneOpen);
+    // Some arbitrary but deterministic walls and costs
+    let cost = |pos: ICoord| match (pos.x * 7 + pos.y * 13).rem_euclid(11) {
+        0 | 1 => None,
+        n => Some(1.0 + n as f64 / 4.0),
+    };
+    let mut incremental = FlowField::new(bounds, movement, cost);
+    incremental.add_goal(ICoord::new(0, 0), 0.0);
+    incremental.add_goal(ICoord::new(7, 5), -3.0);
+    incremental.recompute();
+
+    let changes = [
+        (ICoord::new(2, 2), None),
+        (ICoord::new(4, 1), Some(1.0)),
+        (ICoord::new(0, 0), None),
+        (ICoord::new(0, 0), Some(2.0)),
+        (ICoord::new(5, 3), Some(9.0)),
+        (ICoord::new(-3, -2), None),
+        (ICoord::new(6, 5), None),
+    ];
+    for &(pos, new_cost) in changes.iter() {
+        incremental.set_cost(pos, new_cost);
+
+        let mut full = incremental.clone();
+        full.recompute();
+        for pos in bounds.contained_coords() {
+            match (incremental.distance(pos), full.distance(pos)) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{} {} {}", pos, a, b),
+                (a, b) => assert_eq!(a, b, "{}", pos),
+            }
+        }
+    }
+}
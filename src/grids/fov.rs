@@ -0,0 +1,247 @@
+//! Field of view: figuring out which cells can be seen from a position.
+//!
+//! This uses [recursive shadowcasting](http://www.roguebasin.com/index.php/FOV_using_recursive_shadowcasting),
+//! which I stole from RogueBasin like everyone else.
+//!
+//! Like the pathfinders, these don't store the map; you pass a closure that returns
+//! whether a cell blocks sight. Opaque cells are still visible themselves (you can see walls),
+//! they just hide whatever's behind them.
+//!
+//! ```
+//! # use cogs_gamedev::grids::{fov, ICoord};
+//! let map = [
+//!     ".....",
+//!     ".....",
+//!     "..#..",
+//!     ".....",
+//!     ".....",
+//! ];
+//! let is_opaque = |pos: ICoord| {
+//!     map.get(pos.y as usize)
+//!         .and_then(|row| row.as_bytes().get(pos.x as usize))
+//!         .map_or(true, |c| *c == b'#')
+//! };
+//!
+//! let visible = fov::visible_set(ICoord::new(2, 4), 4, is_opaque);
+//! assert!(visible.contains(&ICoord::new(2, 4)));
+//! // You can see the wall...
+//! assert!(visible.contains(&ICoord::new(2, 2)));
+//! // but not what's behind it.
+//! assert!(!visible.contains(&ICoord::new(2, 1)));
+//! assert!(visible.contains(&ICoord::new(0, 1)));
+//! ```
+
+use super::{Direction8, ICoord};
+
+use ahash::AHashSet;
+
+/// Find every cell visible from `origin` within `radius`, calling `visit` once on each one.
+///
+/// The origin is always visible. Cells are visible if they're within a circle of the given
+/// radius and there's an unobstructed line of sight to them.
+pub fn shadowcast<O, V>(origin: ICoord, radius: usize, mut is_opaque: O, mut visit: V)
+where
+    O: FnMut(ICoord) -> bool,
+    V: FnMut(ICoord),
+{
+    // Octants overlap along the axes and diagonals, so keep track of what we've already sent out
+    let mut seen = AHashSet::new();
+    let mut visit_once = |pos: ICoord| {
+        if seen.insert(pos) {
+            visit(pos);
+        }
+    };
+
+    visit_once(origin);
+    for transform in OCTANTS.iter() {
+        cast_light(
+            origin,
+            radius as isize,
+            1,
+            1.0,
+            0.0,
+            *transform,
+            &mut is_opaque,
+            &mut visit_once,
+        );
+    }
+}
+
+/// Find every cell visible from `origin` within `radius`, and return them all in a set.
+///
+/// See [`shadowcast`].
+pub fn visible_set<O: FnMut(ICoord) -> bool>(
+    origin: ICoord,
+    radius: usize,
+    is_opaque: O,
+) -> AHashSet<ICoord> {
+    let mut out = AHashSet::new();
+    shadowcast(origin, radius, is_opaque, |pos| {
+        out.insert(pos);
+    });
+    out
+}
+
+/**
+Find every cell visible from `origin` within `radius` and inside a cone facing `facing`,
+calling `visit` once on each one.
+
+`half_angle` is in radians, and is how far the cone spreads out to each side of `facing`.
+So, `PI / 4.0` gets you a quarter-circle. The origin is always visible.
+
+```
+# use cogs_gamedev::grids::{fov, Direction8, ICoord};
+use std::f32::consts::PI;
+
+let mut visible = Vec::new();
+fov::shadowcast_cone(
+    ICoord::new(0, 0),
+    3,
+    Direction8::East,
+    PI / 4.0,
+    |_| false,
+    |pos| visible.push(pos),
+);
+assert!(visible.contains(&ICoord::new(0, 0)));
+assert!(visible.contains(&ICoord::new(3, 0)));
+assert!(visible.contains(&ICoord::new(2, 2)));
+assert!(!visible.contains(&ICoord::new(-1, 0)));
+assert!(!visible.contains(&ICoord::new(0, 2)));
+```
+*/
+pub fn shadowcast_cone<O, V>(
+    origin: ICoord,
+    radius: usize,
+    facing: Direction8,
+    half_angle: f32,
+    is_opaque: O,
+    mut visit: V,
+) where
+    O: FnMut(ICoord) -> bool,
+    V: FnMut(ICoord),
+{
+    let facing = facing.radians();
+    shadowcast(origin, radius, is_opaque, |pos| {
+        let delta = pos - origin;
+        if delta == ICoord::new(0, 0) {
+            visit(pos);
+            return;
+        }
+        // This is clockwise with +Y down, the same as `Direction8::radians`
+        let angle = (delta.y as f32).atan2(delta.x as f32);
+        let diff = (angle - facing + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        // fudge factor so cells exactly on the edge count
+        if diff.abs() <= half_angle + 1e-4 {
+            visit(pos);
+        }
+    });
+}
+
+/// Multipliers to transform an octant-local `(dx, dy)` into world space:
+/// `(xx, xy, yx, yy)`.
+const OCTANTS: [(isize, isize, isize, isize); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+/// Scan one octant, row by row, starting at `row` and only looking between the slopes
+/// `start` and `end`. Recurses whenever an opaque cell splits the visible area.
+#[allow(clippy::too_many_arguments)]
+fn cast_light<O, V>(
+    origin: ICoord,
+    radius: isize,
+    row: isize,
+    mut start: f32,
+    end: f32,
+    (xx, xy, yx, yy): (isize, isize, isize, isize),
+    is_opaque: &mut O,
+    visit: &mut V,
+) where
+    O: FnMut(ICoord) -> bool,
+    V: FnMut(ICoord),
+{
+    if start < end {
+        return;
+    }
+    let mut new_start = 0.0;
+    for distance in row..=radius {
+        let dy = -distance;
+        let mut blocked = false;
+        for dx in -distance..=0 {
+            let pos = ICoord::new(origin.x + dx * xx + dy * xy, origin.y + dx * yx + dy * yy);
+            let left_slope = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right_slope = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < right_slope {
+                continue;
+            } else if end > left_slope {
+                break;
+            }
+
+            if dx * dx + dy * dy <= radius * radius {
+                visit(pos);
+            }
+
+            let opaque = is_opaque(pos);
+            if blocked {
+                if opaque {
+                    new_start = right_slope;
+                } else {
+                    blocked = false;
+                    start = new_start;
+                }
+            } else if opaque && distance < radius {
+                blocked = true;
+                cast_light(
+                    origin,
+                    radius,
+                    distance + 1,
+                    start,
+                    left_slope,
+                    (xx, xy, yx, yy),
+                    is_opaque,
+                    visit,
+                );
+                new_start = right_slope;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+#[test]
+fn open_field_is_a_circle() {
+    let origin = ICoord::new(-4, 7);
+    let radius = 6;
+    let visible = visible_set(origin, radius, |_| false);
+    for dx in -10..=10isize {
+        for dy in -10..=10isize {
+            let pos = origin + ICoord::new(dx, dy);
+            let inside = dx * dx + dy * dy <= (radius * radius) as isize;
+            assert_eq!(visible.contains(&pos), inside, "{}", pos);
+        }
+    }
+}
+
+#[test]
+fn walled_room() {
+    // A 5x5 room with walls around the outside: everything inside and the walls are visible,
+    // nothing past them is.
+    let is_opaque = |pos: ICoord| !(1..=5).contains(&pos.x) || !(1..=5).contains(&pos.y);
+    let visible = visible_set(ICoord::new(3, 3), 10, is_opaque);
+    for x in -2..=8 {
+        for y in -2..=8 {
+            let pos = ICoord::new(x, y);
+            let expected = (0..=6).contains(&x) && (0..=6).contains(&y);
+            assert_eq!(visible.contains(&pos), expected, "{}", pos);
+        }
+    }
+}
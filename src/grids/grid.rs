@@ -0,0 +1,455 @@
+//! Dense, rectangular storage for grid-based data.
+
+use super::{Coord, ICoord, IRect};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::ops::{Index, IndexMut};
+
+/// A dense 2D grid of values, stored as one flat `Vec` in reading order.
+///
+/// Positions are converted to indices with [`Coord::to_2d_idx`],
+/// so the top-left corner is `(0, 0)` and `y` increases downwards.
+///
+/// You can index into it with either a [`Coord`] or an [`ICoord`].
+/// Indexing panics when out of bounds; use [`Grid::get`] if you want an `Option` instead.
+///
+/// ```
+/// # use cogs_gamedev::grids::{Coord, Grid, ICoord};
+/// let mut grid = Grid::new(4, 3, '.');
+/// grid[Coord::new(1, 2)] = '#';
+///
+/// assert_eq!(grid[ICoord::new(1, 2)], '#');
+/// assert_eq!(grid.get(ICoord::new(-1, 2)), None);
+/// assert_eq!(grid.get(Coord::new(4, 0)), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GridData<T>"))]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Make a new grid with every cell set to a clone of `fill`.
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Make a new grid, calling the function with each position to get the value there.
+    ///
+    /// The function is called in reading order.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Coord, Grid};
+    /// let grid = Grid::from_fn(3, 2, |pos| pos.x + pos.y * 10);
+    /// assert_eq!(grid.as_slice(), &[0, 1, 2, 10, 11, 12]);
+    /// ```
+    pub fn from_fn<F: FnMut(Coord) -> T>(width: usize, height: usize, mut f: F) -> Self {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(Coord::new(x, y)));
+            }
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Make a grid out of a flat `Vec` in reading order.
+    ///
+    /// Returns `None` if the length of the vec isn't `width * height`.
+    pub fn from_vec(width: usize, height: usize, cells: Vec<T>) -> Option<Self> {
+        if width.checked_mul(height) == Some(cells.len()) {
+            Some(Self {
+                width,
+                height,
+                cells,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The size of the grid as a `Coord`, `(width, height)`.
+    pub fn size(&self) -> Coord {
+        Coord::new(self.width, self.height)
+    }
+
+    /// Get the area covered by this grid.
+    /// The top-left corner is always at `(0, 0)`.
+    pub fn bounds(&self) -> IRect {
+        IRect::new(0, 0, self.width, self.height)
+    }
+
+    /// Is this position inside the grid?
+    pub fn contains<P: Into<ICoord>>(&self, pos: P) -> bool {
+        self.idx(pos).is_some()
+    }
+
+    /// Get the index into the flat storage for this position,
+    /// or `None` if it's out of bounds.
+    pub fn idx<P: Into<ICoord>>(&self, pos: P) -> Option<usize> {
+        let pos = pos.into().to_coord()?;
+        if pos.x < self.width && pos.y < self.height {
+            Some(pos.to_2d_idx(self.width))
+        } else {
+            None
+        }
+    }
+
+    /// Get the value at this position, or `None` if it's out of bounds.
+    pub fn get<P: Into<ICoord>>(&self, pos: P) -> Option<&T> {
+        let idx = self.idx(pos)?;
+        self.cells.get(idx)
+    }
+
+    /// Mutably get the value at this position, or `None` if it's out of bounds.
+    pub fn get_mut<P: Into<ICoord>>(&mut self, pos: P) -> Option<&mut T> {
+        let idx = self.idx(pos)?;
+        self.cells.get_mut(idx)
+    }
+
+    /// Set the value at this position, returning the old value.
+    ///
+    /// Returns `None` and does nothing if the position is out of bounds.
+    pub fn set<P: Into<ICoord>>(&mut self, pos: P, value: T) -> Option<T> {
+        let cell = self.get_mut(pos)?;
+        Some(std::mem::replace(cell, value))
+    }
+
+    /// Iterate over all the values in reading order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    /// Mutably iterate over all the values in reading order.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.cells.iter_mut()
+    }
+
+    /// Iterate over all the values and their positions in reading order.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Coord, Grid};
+    /// let grid = Grid::from_fn(2, 2, |pos| pos.x * pos.y);
+    /// assert_eq!(
+    ///     grid.iter_coords().collect::<Vec<_>>(),
+    ///     vec![
+    ///         (Coord::new(0, 0), &0),
+    ///         (Coord::new(1, 0), &0),
+    ///         (Coord::new(0, 1), &0),
+    ///         (Coord::new(1, 1), &1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn iter_coords(&self) -> impl Iterator<Item = (Coord, &T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(idx, val)| (Coord::new(idx % width, idx / width), val))
+    }
+
+    /// Mutably iterate over all the values and their positions in reading order.
+    pub fn iter_coords_mut(&mut self) -> impl Iterator<Item = (Coord, &mut T)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(idx, val)| (Coord::new(idx % width, idx / width), val))
+    }
+
+    /// Get one row of the grid as a slice, or `None` if `y` is out of bounds.
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        if y < self.height {
+            Some(&self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    /// Mutably get one row of the grid as a slice, or `None` if `y` is out of bounds.
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [T]> {
+        if y < self.height {
+            Some(&mut self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the rows of the grid, top to bottom.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Grid;
+    /// let grid = Grid::from_fn(3, 2, |pos| pos.x + pos.y * 10);
+    /// let mut rows = grid.rows();
+    /// assert_eq!(rows.next(), Some(&[0, 1, 2][..]));
+    /// assert_eq!(rows.next(), Some(&[10, 11, 12][..]));
+    /// assert_eq!(rows.next(), None);
+    /// ```
+    pub fn rows(&self) -> std::slice::ChunksExact<'_, T> {
+        // a 0-width grid has no cells, so chunking by 1 gives nothing.
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    /// Mutably iterate over the rows of the grid, top to bottom.
+    pub fn rows_mut(&mut self) -> std::slice::ChunksExactMut<'_, T> {
+        self.cells.chunks_exact_mut(self.width.max(1))
+    }
+
+    /// Iterate over one column of the grid, top to bottom.
+    ///
+    /// If `x` is out of bounds the iterator is empty.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Grid;
+    /// let grid = Grid::from_fn(3, 2, |pos| pos.x + pos.y * 10);
+    /// assert_eq!(grid.column(1).collect::<Vec<_>>(), vec![&1, &11]);
+    /// assert_eq!(grid.column(3).count(), 0);
+    /// ```
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        let count = if x < self.width { self.height } else { 0 };
+        self.cells
+            .iter()
+            .skip(x)
+            .step_by(self.width.max(1))
+            .take(count)
+    }
+
+    /// Mutably iterate over one column of the grid, top to bottom.
+    ///
+    /// If `x` is out of bounds the iterator is empty.
+    pub fn column_mut(&mut self, x: usize) -> impl Iterator<Item = &mut T> + '_ {
+        let count = if x < self.width { self.height } else { 0 };
+        let step = self.width.max(1);
+        self.cells.iter_mut().skip(x).step_by(step).take(count)
+    }
+
+    /// Iterate over the columns of the grid, left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    /// Set every cell in the grid to a clone of `value`.
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for cell in self.cells.iter_mut() {
+            *cell = value.clone();
+        }
+    }
+
+    /// Set every cell in the given area to a clone of `value`.
+    /// Parts of the rectangle outside the grid are ignored.
+    pub fn fill_rect(&mut self, area: IRect, value: T)
+    where
+        T: Clone,
+    {
+        for pos in area.contained_coords() {
+            if let Some(cell) = self.get_mut(pos) {
+                *cell = value.clone();
+            }
+        }
+    }
+
+    /// Change the size of the grid.
+    ///
+    /// Cells keep their positions; new cells are set to clones of `fill`,
+    /// and cells that end up outside the new size are dropped.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Coord, Grid};
+    /// let mut grid = Grid::from_fn(2, 2, |pos| pos.x + pos.y * 10);
+    /// grid.resize(3, 1, 99);
+    /// assert_eq!(grid.as_slice(), &[0, 1, 99]);
+    /// ```
+    pub fn resize(&mut self, width: usize, height: usize, fill: T)
+    where
+        T: Clone,
+    {
+        if width == self.width {
+            // Easy, just stick things on the end
+            self.cells.resize(width * height, fill);
+        } else {
+            let mut old = std::mem::take(&mut self.cells).into_iter();
+            let mut cells = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for x in 0..width {
+                    if y < self.height && x < self.width {
+                        cells.push(old.next().unwrap());
+                    } else {
+                        cells.push(fill.clone());
+                    }
+                }
+                if y < self.height && width < self.width {
+                    // skip the cells that got chopped off the end of this row
+                    for _ in width..self.width {
+                        old.next();
+                    }
+                }
+            }
+            self.cells = cells;
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Make a new grid of the same size by applying the function to each cell.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Get the flat storage in reading order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Mutably get the flat storage in reading order.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Turn this into the flat storage in reading order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.cells
+    }
+}
+
+impl<T> Index<Coord> for Grid<T> {
+    type Output = T;
+    fn index(&self, index: Coord) -> &Self::Output {
+        match self.get(index) {
+            Some(it) => it,
+            None => panic!(
+                "{} is out of bounds of a {}x{} grid",
+                index, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Coord> for Grid<T> {
+    fn index_mut(&mut self, index: Coord) -> &mut Self::Output {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(index) {
+            Some(it) => it,
+            None => panic!("{} is out of bounds of a {}x{} grid", index, width, height),
+        }
+    }
+}
+
+impl<T> Index<ICoord> for Grid<T> {
+    type Output = T;
+    fn index(&self, index: ICoord) -> &Self::Output {
+        match self.get(index) {
+            Some(it) => it,
+            None => panic!(
+                "{} is out of bounds of a {}x{} grid",
+                index, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<ICoord> for Grid<T> {
+    fn index_mut(&mut self, index: ICoord) -> &mut Self::Output {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(index) {
+            Some(it) => it,
+            None => panic!("{} is out of bounds of a {}x{} grid", index, width, height),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Grid<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Grid<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// What a [`Grid`] gets deserialized from, before checking that the cells fit.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "Grid")]
+struct GridData<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<GridData<T>> for Grid<T> {
+    type Error = &'static str;
+    fn try_from(data: GridData<T>) -> Result<Self, Self::Error> {
+        Grid::from_vec(data.width, data.height, data.cells)
+            .ok_or("the number of cells in a grid must be its width times its height")
+    }
+}
+
+#[test]
+fn resize_keeps_positions() {
+    let mut grid = Grid::from_fn(3, 3, |pos| pos.x + pos.y * 10);
+
+    grid.resize(4, 2, 99);
+    assert_eq!(grid.as_slice(), &[0, 1, 2, 99, 10, 11, 12, 99]);
+
+    grid.resize(2, 3, 77);
+    assert_eq!(grid.as_slice(), &[0, 1, 10, 11, 77, 77]);
+    assert_eq!(grid.bounds(), IRect::new(0, 0, 2, 3));
+
+    grid.resize(0, 5, 0);
+    assert_eq!(grid.as_slice(), &[] as &[usize]);
+    assert_eq!(grid.rows().count(), 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialize_checks_size() {
+    let data = |width, height, cells| GridData {
+        width,
+        height,
+        cells,
+    };
+    assert!(Grid::try_from(data(2, 2, vec![1, 2, 3, 4])).is_ok());
+    assert!(Grid::try_from(data(2, 2, vec![1, 2, 3])).is_err());
+    assert!(Grid::try_from(data(usize::MAX, 2, vec![1, 2])).is_err());
+}
@@ -0,0 +1,471 @@
+//! Hexagonal grids.
+//!
+//! This is all based on the *excellent* [guide by Red Blob Games](https://www.redblobgames.com/grids/hexagons/),
+//! which you should read if you have any questions about how this works.
+//!
+//! [`HexCoord`]s use axial coordinates. The third cube coordinate `s` is always `-q - r`,
+//! so it isn't stored.
+
+use super::{ICoord, Rotation};
+use crate::ease::Interpolator;
+
+use enum_map::Enum;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
+};
+
+/// Which way up the hexagons are.
+///
+/// This matters for converting to and from pixels and offset coordinates,
+/// and for which way the [`HexDirection`]s point.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HexLayout {
+    /// The hexagons have a point at the top, so they're arranged in horizontal rows.
+    PointyTop,
+    /// The hexagons have a flat edge at the top, so they're arranged in vertical columns.
+    FlatTop,
+}
+
+/// Six-way directions on a hex grid.
+///
+/// These are named for a [`HexLayout::PointyTop`] layout.
+/// With [`HexLayout::FlatTop`] the whole grid is turned 30 degrees clockwise,
+/// so `East` really points east-south-east, `NorthEast` points north-north-east, and so on.
+///
+/// These start at NorthEast and increment clockwise,
+/// so you can convert them to integers with `as` and use them
+/// in rotational calculations if you need.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Enum)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HexDirection {
+    NorthEast,
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl HexDirection {
+    /// All the directions in order.
+    /// This is used internally for rotations and flips.
+    /// I made it public just in case it's helpful for you the programmer.
+    pub const DIRECTIONS: [HexDirection; 6] = [
+        HexDirection::NorthEast,
+        HexDirection::East,
+        HexDirection::SouthEast,
+        HexDirection::SouthWest,
+        HexDirection::West,
+        HexDirection::NorthWest,
+    ];
+
+    /// Rotate this by the given amount.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexDirection, Rotation};
+    /// use HexDirection::*;
+    /// use Rotation::*;
+    ///
+    /// assert_eq!(NorthEast.rotate(Clockwise), East);
+    /// assert_eq!(NorthEast.rotate(CounterClockwise), NorthWest);
+    /// ```
+    pub fn rotate(self, rot: Rotation) -> Self {
+        self.rotate_by(rot.steps_clockwise())
+    }
+
+    /// Get this direction, rotated by this many steps clockwise.
+    /// Negative numbers go counter-clockwise.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexDirection;
+    /// use HexDirection::*;
+    /// assert_eq!(East.rotate_by(1), SouthEast);
+    /// assert_eq!(East.rotate_by(3), West);
+    /// assert_eq!(East.rotate_by(-2), NorthWest);
+    /// assert_eq!(East.rotate_by(5).rotate_by(-11), East);
+    /// ```
+    pub fn rotate_by(self, steps_clockwise: isize) -> Self {
+        let idx = self as isize;
+        let new_idx =
+            ((idx + steps_clockwise).rem_euclid(Self::DIRECTIONS.len() as isize)) as usize;
+        Self::DIRECTIONS[new_idx]
+    }
+
+    /// Flip this direction.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexDirection;
+    /// use HexDirection::*;
+    /// assert_eq!(East.flip(), West);
+    /// assert_eq!(NorthEast.flip(), SouthWest);
+    /// assert_eq!(SouthEast.flip().flip(), SouthEast);
+    /// ```
+    pub fn flip(self) -> Self {
+        self.rotate_by(3)
+    }
+
+    /// Get this direction in radians.
+    ///
+    /// This uses the same standard as [`Direction4::radians`]: 0 radians is to the right,
+    /// and positive radians increment *clockwise*.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexDirection, HexLayout};
+    /// use HexDirection::*;
+    /// use std::f32::consts::TAU;
+    ///
+    /// assert!(East.radians(HexLayout::PointyTop).abs() < 1e-6);
+    /// assert!((NorthEast.radians(HexLayout::PointyTop) - (TAU / 6.0 * 5.0)).abs() < 1e-6);
+    /// assert!((East.radians(HexLayout::FlatTop) - (TAU / 12.0)).abs() < 1e-6);
+    /// ```
+    ///
+    /// [`Direction4::radians`]: super::Direction4::radians
+    pub fn radians(self, layout: HexLayout) -> f32 {
+        let pointy = ((self as i8) - 1).rem_euclid(6) as f32 * std::f32::consts::TAU / 6.0;
+        match layout {
+            HexLayout::PointyTop => pointy,
+            HexLayout::FlatTop => {
+                (pointy + std::f32::consts::TAU / 12.0).rem_euclid(std::f32::consts::TAU)
+            }
+        }
+    }
+
+    /// Get the deltas a step in this direction would result in, as a HexCoord.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexCoord, HexDirection};
+    /// use HexDirection::*;
+    ///
+    /// assert_eq!(East.deltas(), HexCoord::new(1, 0));
+    /// assert_eq!(NorthWest.deltas(), HexCoord::new(0, -1));
+    /// ```
+    pub fn deltas(self) -> HexCoord {
+        let (q, r) = match self {
+            HexDirection::NorthEast => (1, -1),
+            HexDirection::East => (1, 0),
+            HexDirection::SouthEast => (0, 1),
+            HexDirection::SouthWest => (-1, 1),
+            HexDirection::West => (-1, 0),
+            HexDirection::NorthWest => (0, -1),
+        };
+        HexCoord { q, r }
+    }
+}
+
+/// Coordinates on a hex grid, in axial form.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HexCoord {
+    pub q: isize,
+    pub r: isize,
+}
+
+impl HexCoord {
+    /// Make a new HexCoord.
+    pub fn new(q: isize, r: isize) -> Self {
+        Self { q, r }
+    }
+
+    /// Get the third cube coordinate, `-q - r`.
+    pub fn s(self) -> isize {
+        -self.q - self.r
+    }
+
+    /// Round fractional axial coordinates to the hex they're in.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexCoord;
+    /// assert_eq!(HexCoord::round(0.1, -0.2), HexCoord::new(0, 0));
+    /// assert_eq!(HexCoord::round(0.6, 0.6), HexCoord::new(1, 0));
+    /// assert_eq!(HexCoord::round(-1.4, 0.45), HexCoord::new(-1, 0));
+    /// ```
+    pub fn round(q: f32, r: f32) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+        // Fix up whichever one rounded the furthest so they add to 0 again
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+        Self::new(rq as isize, rr as isize)
+    }
+
+    /// Get a list of this coordinate's neighbors.
+    /// They are given in clockwise order starting with the neighbor to the north-east,
+    /// as if each of [`HexDirection::DIRECTIONS`] had been added to them.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexCoord, HexDirection};
+    /// let origin = HexCoord::new(-7, 12);
+    /// assert_eq!(
+    ///     origin.neighbors()[..],
+    ///     HexDirection::DIRECTIONS.iter().map(|dir| origin + *dir).collect::<Vec<_>>()[..],
+    /// );
+    /// ```
+    pub fn neighbors(self) -> [HexCoord; 6] {
+        [
+            self + HexDirection::NorthEast,
+            self + HexDirection::East,
+            self + HexDirection::SouthEast,
+            self + HexDirection::SouthWest,
+            self + HexDirection::West,
+            self + HexDirection::NorthWest,
+        ]
+    }
+
+    /// Get the number of steps it takes to get from this to the other coordinate.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexCoord;
+    /// assert_eq!(HexCoord::new(0, 0).distance(HexCoord::new(3, -1)), 3);
+    /// assert_eq!(HexCoord::new(1, 1).distance(HexCoord::new(-1, 0)), 3);
+    /// ```
+    pub fn distance(self, other: HexCoord) -> usize {
+        let delta = self - other;
+        (delta.q.abs() + delta.r.abs() + delta.s().abs()) as usize / 2
+    }
+
+    /// Get all the hexes exactly `radius` steps away from this one.
+    ///
+    /// There are `6 * radius` of them (or just this hex, if the radius is 0).
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexCoord;
+    /// let center = HexCoord::new(2, -5);
+    /// let ring = center.ring(3);
+    /// assert_eq!(ring.len(), 18);
+    /// assert!(ring.iter().all(|hex| hex.distance(center) == 3));
+    /// ```
+    pub fn ring(self, radius: usize) -> Vec<HexCoord> {
+        if radius == 0 {
+            return vec![self];
+        }
+        let mut out = Vec::with_capacity(6 * radius);
+        let mut cursor = self + HexDirection::SouthWest.deltas() * radius as isize;
+        let mut dir = HexDirection::East;
+        for _ in 0..6 {
+            for _ in 0..radius {
+                out.push(cursor);
+                cursor += dir;
+            }
+            dir = dir.rotate_by(-1);
+        }
+        out
+    }
+
+    /// Get all the hexes within `radius` steps of this one,
+    /// starting with this hex and spiralling outwards ring by ring.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexCoord;
+    /// let center = HexCoord::new(0, 0);
+    /// let spiral = center.spiral(2);
+    /// assert_eq!(spiral.len(), 19);
+    /// assert_eq!(spiral[0], center);
+    /// assert!(spiral.iter().all(|hex| hex.distance(center) <= 2));
+    /// ```
+    pub fn spiral(self, radius: usize) -> Vec<HexCoord> {
+        (0..=radius).flat_map(|ring| self.ring(ring)).collect()
+    }
+
+    /// Get the hexes on a straight line from this to the other coordinate, including both ends.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::HexCoord;
+    /// let line = HexCoord::new(0, 0).line_to(HexCoord::new(4, -2));
+    /// assert_eq!(line.len(), 5);
+    /// for pair in line.windows(2) {
+    ///     assert_eq!(pair[0].distance(pair[1]), 1);
+    /// }
+    /// ```
+    pub fn line_to(self, other: HexCoord) -> Vec<HexCoord> {
+        let distance = self.distance(other);
+        if distance == 0 {
+            return vec![self];
+        }
+        // Nudge things slightly so lines exactly on edges go consistently to one side
+        let (q1, r1) = (self.q as f32 + 1e-6, self.r as f32 + 2e-6);
+        let (q2, r2) = (other.q as f32 + 1e-6, other.r as f32 + 2e-6);
+        (0..=distance)
+            .map(|i| {
+                let t = i as f32 / distance as f32;
+                HexCoord::round(t.lerp(q1, q2), t.lerp(r1, r2))
+            })
+            .collect()
+    }
+
+    /// Convert this to offset coordinates, where each row (for [`HexLayout::PointyTop`])
+    /// or column (for [`HexLayout::FlatTop`]) is stored as one line of an ordinary grid.
+    ///
+    /// This uses the "odd" convention: odd rows are shoved right half a hex
+    /// (or odd columns are shoved down half a hex).
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexCoord, HexLayout, ICoord};
+    /// let hex = HexCoord::new(-2, 3);
+    /// let offset = hex.to_offset(HexLayout::PointyTop);
+    /// assert_eq!(offset, ICoord::new(-1, 3));
+    /// assert_eq!(HexCoord::from_offset(offset, HexLayout::PointyTop), hex);
+    /// ```
+    pub fn to_offset(self, layout: HexLayout) -> ICoord {
+        match layout {
+            HexLayout::PointyTop => ICoord::new(self.q + (self.r - (self.r & 1)) / 2, self.r),
+            HexLayout::FlatTop => ICoord::new(self.q, self.r + (self.q - (self.q & 1)) / 2),
+        }
+    }
+
+    /// Convert offset coordinates into a HexCoord.
+    ///
+    /// See [`HexCoord::to_offset`] for the convention used.
+    pub fn from_offset(offset: ICoord, layout: HexLayout) -> Self {
+        match layout {
+            HexLayout::PointyTop => Self::new(offset.x - (offset.y - (offset.y & 1)) / 2, offset.y),
+            HexLayout::FlatTop => Self::new(offset.x, offset.y - (offset.x - (offset.x & 1)) / 2),
+        }
+    }
+
+    /// Get the pixel position of the center of this hex, as `[x, y]`.
+    ///
+    /// `size` is the distance from the center of a hex to one of its corners.
+    /// The hex at `(0, 0)` is centered on the origin.
+    pub fn to_pixel(self, layout: HexLayout, size: f32) -> [f32; 2] {
+        let sqrt3 = 3.0f32.sqrt();
+        let (q, r) = (self.q as f32, self.r as f32);
+        match layout {
+            HexLayout::PointyTop => [size * (sqrt3 * q + sqrt3 / 2.0 * r), size * (1.5 * r)],
+            HexLayout::FlatTop => [size * (1.5 * q), size * (sqrt3 / 2.0 * q + sqrt3 * r)],
+        }
+    }
+
+    /// Get the hex containing the given pixel position.
+    ///
+    /// `size` is the distance from the center of a hex to one of its corners.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{HexCoord, HexLayout};
+    /// for &layout in &[HexLayout::PointyTop, HexLayout::FlatTop] {
+    ///     let hex = HexCoord::new(3, -7);
+    ///     let [x, y] = hex.to_pixel(layout, 16.0);
+    ///     assert_eq!(HexCoord::from_pixel([x + 5.0, y - 3.0], layout, 16.0), hex);
+    /// }
+    /// ```
+    pub fn from_pixel(pixel: [f32; 2], layout: HexLayout, size: f32) -> Self {
+        let sqrt3 = 3.0f32.sqrt();
+        let [x, y] = [pixel[0] / size, pixel[1] / size];
+        let (q, r) = match layout {
+            HexLayout::PointyTop => (sqrt3 / 3.0 * x - y / 3.0, 2.0 / 3.0 * y),
+            HexLayout::FlatTop => (2.0 / 3.0 * x, -x / 3.0 + sqrt3 / 3.0 * y),
+        };
+        Self::round(q, r)
+    }
+}
+
+impl Add for HexCoord {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q + rhs.q,
+            r: self.r + rhs.r,
+        }
+    }
+}
+
+impl AddAssign for HexCoord {
+    fn add_assign(&mut self, rhs: Self) {
+        self.q += rhs.q;
+        self.r += rhs.r;
+    }
+}
+
+impl Sub for HexCoord {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            q: self.q - rhs.q,
+            r: self.r - rhs.r,
+        }
+    }
+}
+
+impl SubAssign for HexCoord {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.q -= rhs.q;
+        self.r -= rhs.r;
+    }
+}
+
+impl Add<HexDirection> for HexCoord {
+    type Output = Self;
+    fn add(self, rhs: HexDirection) -> Self::Output {
+        self + rhs.deltas()
+    }
+}
+
+impl AddAssign<HexDirection> for HexCoord {
+    fn add_assign(&mut self, rhs: HexDirection) {
+        *self += rhs.deltas();
+    }
+}
+
+impl Mul<isize> for HexCoord {
+    type Output = Self;
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self {
+            q: self.q * rhs,
+            r: self.r * rhs,
+        }
+    }
+}
+
+impl MulAssign<isize> for HexCoord {
+    fn mul_assign(&mut self, rhs: isize) {
+        self.q *= rhs;
+        self.r *= rhs;
+    }
+}
+
+impl Display for HexCoord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.q, self.r)
+    }
+}
+
+#[test]
+fn offset_roundtrip() {
+    for &layout in &[HexLayout::PointyTop, HexLayout::FlatTop] {
+        for hex in HexCoord::new(-1, 2).spiral(6) {
+            let offset = hex.to_offset(layout);
+            assert_eq!(HexCoord::from_offset(offset, layout), hex);
+        }
+        // Neighbors in offset space are still neighbors
+        let origin = HexCoord::new(0, 0);
+        for neighbor in origin.neighbors().iter() {
+            let delta = neighbor.to_offset(layout) - origin.to_offset(layout);
+            assert!(delta.x.abs() <= 1 && delta.y.abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn pixel_directions() {
+    // The pixel offset to each neighbor should match the direction's angle
+    for &layout in &[HexLayout::PointyTop, HexLayout::FlatTop] {
+        for dir in HexDirection::DIRECTIONS.iter() {
+            let [x, y] = dir.deltas().to_pixel(layout, 1.0);
+            let angle = y.atan2(x).rem_euclid(std::f32::consts::TAU);
+            assert!(
+                (angle - dir.radians(layout)).abs() < 1e-5,
+                "{:?} {:?}",
+                layout,
+                dir
+            );
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Lines and rays through grids.
+
+use super::{Direction4, ICoord};
+
+/// Iterator over the cells of a [Bresenham line](https://en.wikipedia.org/wiki/Bresenham%27s_line_algorithm)
+/// between two positions, including both ends.
+///
+/// Each cell is one step (possibly diagonal) from the last.
+///
+/// Get one with [`ICoord::line_to`].
+#[derive(Debug, Clone)]
+pub struct LineIter {
+    cursor: ICoord,
+    end: ICoord,
+    delta: ICoord,
+    step: ICoord,
+    error: isize,
+    exhausted: bool,
+}
+
+impl LineIter {
+    pub fn new(start: ICoord, end: ICoord) -> Self {
+        let dx = (end.x - start.x).abs();
+        let dy = -(end.y - start.y).abs();
+        Self {
+            cursor: start,
+            end,
+            delta: ICoord::new(dx, dy),
+            step: ICoord::new((end.x - start.x).signum(), (end.y - start.y).signum()),
+            error: dx + dy,
+            exhausted: false,
+        }
+    }
+}
+
+impl Iterator for LineIter {
+    type Item = ICoord;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+        let out = self.cursor;
+        if self.cursor == self.end {
+            self.exhausted = true;
+        } else {
+            let doubled = self.error * 2;
+            if doubled >= self.delta.y {
+                self.error += self.delta.y;
+                self.cursor.x += self.step.x;
+            }
+            if doubled <= self.delta.x {
+                self.error += self.delta.x;
+                self.cursor.y += self.step.y;
+            }
+        }
+        Some(out)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = if self.exhausted {
+            0
+        } else {
+            let remaining = self.end - self.cursor;
+            remaining.x.abs().max(remaining.y.abs()) as usize + 1
+        };
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for LineIter {}
+
+/// Iterator over every cell touched by the line segment between the centers of two cells,
+/// including both ends.
+///
+/// Each cell is one orthogonal step from the last, except where the line passes exactly
+/// through a corner. Then both cells beside the corner are included, the horizontal one first,
+/// so there's one diagonal step between those two. (There's no way to fit all four cells
+/// around a corner into orthogonal steps without visiting one twice.)
+///
+/// Get one with [`ICoord::supercover_line_to`].
+#[derive(Debug, Clone)]
+pub struct SupercoverIter {
+    cursor: ICoord,
+    steps: ICoord,
+    taken: ICoord,
+    step: ICoord,
+    /// Cells to output before moving on, popped from the back
+    pending: Vec<ICoord>,
+    started: bool,
+}
+
+impl SupercoverIter {
+    pub fn new(start: ICoord, end: ICoord) -> Self {
+        let delta = end - start;
+        Self {
+            cursor: start,
+            steps: ICoord::new(delta.x.abs(), delta.y.abs()),
+            taken: ICoord::new(0, 0),
+            step: ICoord::new(delta.x.signum(), delta.y.signum()),
+            pending: Vec::new(),
+            started: false,
+        }
+    }
+}
+
+impl Iterator for SupercoverIter {
+    type Item = ICoord;
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some(self.cursor);
+        }
+        if let Some(pending) = self.pending.pop() {
+            return Some(pending);
+        }
+        if self.taken.x >= self.steps.x && self.taken.y >= self.steps.y {
+            return None;
+        }
+
+        // Compare where the line crosses the next vertical and horizontal edges
+        let decision =
+            (1 + 2 * self.taken.x) * self.steps.y - (1 + 2 * self.taken.y) * self.steps.x;
+        if decision == 0 {
+            // Right through the corner
+            let horiz = self.cursor + ICoord::new(self.step.x, 0);
+            let vert = self.cursor + ICoord::new(0, self.step.y);
+            self.cursor += self.step;
+            self.taken += ICoord::new(1, 1);
+            self.pending.push(self.cursor);
+            self.pending.push(vert);
+            Some(horiz)
+        } else {
+            if decision < 0 {
+                self.cursor.x += self.step.x;
+                self.taken.x += 1;
+            } else {
+                self.cursor.y += self.step.y;
+                self.taken.y += 1;
+            }
+            Some(self.cursor)
+        }
+    }
+}
+
+/// Where a ray cast with [`raycast`] hit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// The cell that was hit.
+    pub cell: ICoord,
+    /// The face of the cell the ray entered through.
+    /// For example, a ray going east hits the west face of a cell.
+    pub face: Direction4,
+    /// How far along the ray the hit was, in cells.
+    pub distance: f32,
+}
+
+/**
+Cast a ray through the grid and find the first cell it hits, using the
+[DDA algorithm](https://lodev.org/cgtutor/raycasting.html).
+
+Each cell `(x, y)` covers the square from `(x, y)` to `(x + 1, y + 1)`,
+so the center of the cell at the origin is `[0.5, 0.5]`.
+`direction` doesn't need to be normalized.
+
+The cell the ray starts in is never checked.
+Returns `None` if the ray goes further than `max_distance` without hitting anything,
+or if `direction` is zero.
+
+`max_distance` has to be finite, because otherwise a ray that never hits anything
+would go on forever. If it (or anything in `origin` or `direction`) is infinite or NaN,
+this returns `None` without casting anything.
+
+```
+# use cogs_gamedev::grids::{raycast, Direction4, ICoord};
+let wall = |pos: ICoord| pos.x >= 5;
+
+let hit = raycast([0.5, 0.5], [1.0, 0.0], 100.0, wall).unwrap();
+assert_eq!(hit.cell, ICoord::new(5, 0));
+assert_eq!(hit.face, Direction4::West);
+assert!((hit.distance - 4.5).abs() < 1e-5);
+
+assert!(raycast([0.5, 0.5], [1.0, 0.0], 3.0, wall).is_none());
+assert!(raycast([0.5, 0.5], [-1.0, 0.0], 100.0, wall).is_none());
+assert!(raycast([0.5, 0.5], [1.0, 0.0], f32::INFINITY, wall).is_none());
+```
+*/
+pub fn raycast<S: FnMut(ICoord) -> bool>(
+    origin: [f32; 2],
+    direction: [f32; 2],
+    max_distance: f32,
+    mut is_solid: S,
+) -> Option<RaycastHit> {
+    let length = direction[0].hypot(direction[1]);
+    if length == 0.0
+        || !length.is_finite()
+        || !max_distance.is_finite()
+        || !origin.iter().all(|it| it.is_finite())
+    {
+        return None;
+    }
+    let dir = [direction[0] / length, direction[1] / length];
+
+    let mut cell = ICoord::new(origin[0].floor() as isize, origin[1].floor() as isize);
+    // How far along the ray you have to go to cross one whole cell on each axis.
+    // The distances add up in f64, because far enough out adding one cell's worth
+    // to an f32 wouldn't change it at all, and the ray would never reach `max_distance`.
+    let delta_dist = [(dir[0] as f64).recip().abs(), (dir[1] as f64).recip().abs()];
+    let step = ICoord::new(dir[0].signum() as isize, dir[1].signum() as isize);
+    // How far along the ray the next edge on each axis is
+    let mut side_dist = [0.0; 2];
+    for axis in 0..2 {
+        let cell_pos = if axis == 0 { cell.x } else { cell.y } as f64;
+        let origin = origin[axis] as f64;
+        side_dist[axis] = if dir[axis] < 0.0 {
+            (origin - cell_pos) * delta_dist[axis]
+        } else {
+            (cell_pos + 1.0 - origin) * delta_dist[axis]
+        };
+    }
+
+    loop {
+        let (distance, face) = if side_dist[0] < side_dist[1] {
+            let out = side_dist[0];
+            side_dist[0] += delta_dist[0];
+            cell.x += step.x;
+            let face = if step.x > 0 {
+                Direction4::West
+            } else {
+                Direction4::East
+            };
+            (out, face)
+        } else {
+            let out = side_dist[1];
+            side_dist[1] += delta_dist[1];
+            cell.y += step.y;
+            let face = if step.y > 0 {
+                Direction4::North
+            } else {
+                Direction4::South
+            };
+            (out, face)
+        };
+
+        if distance > max_distance as f64 {
+            return None;
+        }
+        if is_solid(cell) {
+            return Some(RaycastHit {
+                cell,
+                face,
+                distance: distance as f32,
+            });
+        }
+    }
+}
+
+#[test]
+fn lines() {
+    for &(start, end) in &[
+        (ICoord::new(0, 0), ICoord::new(5, 2)),
+        (ICoord::new(3, -4), ICoord::new(-7, 1)),
+        (ICoord::new(1, 1), ICoord::new(1, 1)),
+        (ICoord::new(-2, 6), ICoord::new(-2, -3)),
+        (ICoord::new(0, 0), ICoord::new(-4, -4)),
+    ] {
+        let line = start.line_to(end).collect::<Vec<_>>();
+        assert_eq!(line.len(), start.line_to(end).len());
+        assert_eq!(line.first(), Some(&start));
+        assert_eq!(line.last(), Some(&end));
+        for pair in line.windows(2) {
+            let d = pair[1] - pair[0];
+            assert!(d.x.abs() <= 1 && d.y.abs() <= 1 && d != ICoord::new(0, 0));
+        }
+
+        let cover = start.supercover_line_to(end).collect::<Vec<_>>();
+        assert_eq!(cover.first(), Some(&start));
+        assert_eq!(cover.last(), Some(&end));
+        for (idx, pair) in cover.windows(2).enumerate() {
+            let d = pair[1] - pair[0];
+            if d.x.abs() + d.y.abs() != 1 {
+                // The only diagonal step is between the two cells beside a corner,
+                // which make a 2x2 square with the cells before and after them
+                assert!(d.x.abs() == 1 && d.y.abs() == 1, "{} -> {}", start, end);
+                let (before, after) = (cover[idx - 1], cover[idx + 2]);
+                assert_eq!(before + after, pair[0] + pair[1]);
+                assert_eq!((pair[0] - before).x.abs(), 1);
+            }
+        }
+        // Supercover lines include every cell the bresenham line does
+        for pos in line.iter() {
+            assert!(cover.contains(pos), "{} -> {} missing {}", start, end, pos);
+        }
+    }
+
+    assert_eq!(
+        ICoord::new(0, 0)
+            .supercover_line_to(ICoord::new(2, 2))
+            .collect::<Vec<_>>(),
+        vec![
+            ICoord::new(0, 0),
+            ICoord::new(1, 0),
+            ICoord::new(0, 1),
+            ICoord::new(1, 1),
+            ICoord::new(2, 1),
+            ICoord::new(1, 2),
+            ICoord::new(2, 2),
+        ]
+    );
+}
+
+#[test]
+fn raycast_faces() {
+    let solid = |pos: ICoord| pos == ICoord::new(3, 3);
+    let hit = raycast([3.5, 0.5], [0.0, 1.0], 10.0, solid).unwrap();
+    assert_eq!(hit.face, Direction4::North);
+    assert!((hit.distance - 2.5).abs() < 1e-5);
+
+    let hit = raycast([3.5, 8.5], [0.0, -3.0], 10.0, solid).unwrap();
+    assert_eq!(hit.face, Direction4::South);
+    assert!((hit.distance - 4.5).abs() < 1e-5);
+
+    let hit = raycast([0.5, 0.5], [1.0, 1.0], 10.0, solid).unwrap();
+    assert_eq!(hit.cell, ICoord::new(3, 3));
+
+    let hit = raycast([8.0, 3.25], [-1.0, 0.0], 10.0, solid).unwrap();
+    assert_eq!(hit.face, Direction4::East);
+    assert!((hit.distance - 4.0).abs() < 1e-5);
+
+    // Further than an f32 can count in single cells, it still gets there (and stops)
+    let far = |pos: ICoord| pos.x >= 20_000_000;
+    let hit = raycast([0.5, 0.5], [1.0, 0.0], 1e9, far).unwrap();
+    assert_eq!(hit.cell, ICoord::new(20_000_000, 0));
+    assert!(raycast([0.5, 0.5], [1.0, 0.0], 19_999_000.0, far).is_none());
+}
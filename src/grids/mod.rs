@@ -1,8 +1,26 @@
 //! Utilities for working with anything based on a grid.
 
+pub mod automata;
+pub use automata::*;
+pub mod chunked;
+pub use chunked::*;
 pub mod coords;
 pub use coords::*;
 pub mod directions;
 pub use directions::*;
+pub mod dungeon;
+pub mod flow_field;
+pub use flow_field::*;
+pub mod fov;
+pub mod grid;
+pub use grid::*;
 pub mod rectangles;
 pub use rectangles::*;
+pub mod vectors;
+pub use vectors::*;
+pub mod hex;
+pub use hex::*;
+pub mod lines;
+pub use lines::*;
+pub mod pathfinding;
+pub mod wfc;
@@ -0,0 +1,463 @@
+//! Finding paths between positions on a grid.
+//!
+//! All the pathfinders here work on [`ICoord`]s and don't store the map themselves.
+//! Instead, you pass a closure that tells them what each cell is like.
+//!
+//! - [`astar`] and [`dijkstra`] take a closure returning the cost to *enter* a cell,
+//!   or `None` if the cell can't be entered at all.
+//! - [`bfs`] takes a closure returning whether a cell can be entered; every step costs the same.
+//!
+//! Because `ICoord`s go on forever, the closure is responsible for saying where the map ends.
+//! If it says infinitely many cells are passable and there's no way to reach the goal,
+//! the search will never finish.
+//!
+//! Paths include both the start and end positions.
+//! You can turn them into lists of steps with [`steps4`] and [`steps8`].
+//!
+//! ```
+//! # use cogs_gamedev::grids::{ICoord, Direction4};
+//! # use cogs_gamedev::grids::pathfinding::{self, Heuristic, Movement};
+//! let map = [
+//!     "....",
+//!     ".##.",
+//!     "..#.",
+//! ];
+//! let cost = |pos: ICoord| {
+//!     let row = map.get(pos.y as usize)?;
+//!     match row.as_bytes().get(pos.x as usize)? {
+//!         b'.' => Some(1.0),
+//!         _ => None,
+//!     }
+//! };
+//!
+//! let (path, cost) = pathfinding::astar(
+//!     ICoord::new(0, 2),
+//!     ICoord::new(3, 2),
+//!     Movement::Four,
+//!     Heuristic::Manhattan,
+//!     cost,
+//! )
+//! .unwrap();
+//! assert_eq!(path.len(), 8);
+//! assert_eq!(cost, 7.0);
+//!
+//! use Direction4::*;
+//! assert_eq!(
+//!     pathfinding::steps4(&path).unwrap(),
+//!     vec![North, North, East, East, East, South, South],
+//! );
+//! ```
+
+use super::{Direction4, Direction8, ICoord};
+
+use ahash::{AHashMap, AHashSet};
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
+
+/// Estimates of the distance between two positions, for use with [`astar`].
+///
+/// They all assume that each step costs at least 1.
+/// If your cells can cost less than that, the paths A* finds might not be the shortest.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Heuristic {
+    /// `dx + dy`. Use this with [`Movement::Four`].
+    Manhattan,
+    /// `max(dx, dy)`. This works with [`Movement::Eight`], but it underestimates
+    /// a lot because diagonal steps cost `sqrt(2)`, so it's slower than [`Heuristic::Octile`].
+    Chebyshev,
+    /// `max(dx, dy) + (sqrt(2) - 1) * min(dx, dy)`. Use this with [`Movement::Eight`].
+    Octile,
+    /// Straight-line distance.
+    Euclidean,
+}
+
+impl Heuristic {
+    /// Get the estimated distance between the two positions.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::ICoord;
+    /// # use cogs_gamedev::grids::pathfinding::Heuristic;
+    /// let a = ICoord::new(0, 0);
+    /// let b = ICoord::new(3, -4);
+    /// assert_eq!(Heuristic::Manhattan.distance(a, b), 7.0);
+    /// assert_eq!(Heuristic::Chebyshev.distance(a, b), 4.0);
+    /// assert_eq!(Heuristic::Euclidean.distance(a, b), 5.0);
+    /// ```
+    pub fn distance(self, a: ICoord, b: ICoord) -> f64 {
+        let dx = (a.x - b.x).abs() as f64;
+        let dy = (a.y - b.y).abs() as f64;
+        match self {
+            Heuristic::Manhattan => dx + dy,
+            Heuristic::Chebyshev => dx.max(dy),
+            Heuristic::Octile => dx.max(dy) + (std::f64::consts::SQRT_2 - 1.0) * dx.min(dy),
+            Heuristic::Euclidean => dx.hypot(dy),
+        }
+    }
+}
+
+/// What to do when moving diagonally past a blocked cell.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CornerCutting {
+    /// Diagonal moves are always allowed, even squeezing between two blocked cells.
+    Always,
+    /// Diagonal moves are allowed if at least one of the two cells beside the move is open.
+    IfOneOpen,
+    /// Diagonal moves are only allowed if both of the cells beside the move are open.
+    Never,
+}
+
+/// How things are allowed to move around the grid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Movement {
+    /// Only move in the [`Direction4`]s.
+    Four,
+    /// Move in all the [`Direction8`]s.
+    /// Diagonal moves cost `sqrt(2)` times the cost of the cell moved into.
+    Eight(CornerCutting),
+}
+
+impl Movement {
+    /// All the directions this lets you step in, in clockwise order starting with North.
+    pub fn directions(self) -> &'static [Direction8] {
+        use Direction8::*;
+        match self {
+            Movement::Four => &[North, East, South, West],
+            Movement::Eight(_) => &Direction8::DIRECTIONS,
+        }
+    }
+
+    /// Get how much it costs to take one step from `pos` in the given direction,
+    /// or `None` if that step isn't allowed.
+    ///
+    /// `cost` should return the cost to move into a cell, or `None` if it can't be entered.
+    /// `pos` itself is never checked.
+    pub fn step_cost<C: FnMut(ICoord) -> Option<f64>>(
+        self,
+        pos: ICoord,
+        dir: Direction8,
+        cost: &mut C,
+    ) -> Option<f64> {
+        let deltas = dir.deltas();
+        let diagonal = deltas.x != 0 && deltas.y != 0;
+        let corners = match self {
+            Movement::Four if diagonal => return None,
+            Movement::Four => CornerCutting::Always,
+            Movement::Eight(corners) => corners,
+        };
+
+        let step_cost = cost(pos + dir)?;
+        if !diagonal {
+            return Some(step_cost);
+        }
+        let open_sides = [dir.rotate_by(-1), dir.rotate_by(1)]
+            .iter()
+            .filter(|side| cost(pos + **side).is_some())
+            .count();
+        let allowed = match corners {
+            CornerCutting::Always => true,
+            CornerCutting::IfOneOpen => open_sides >= 1,
+            CornerCutting::Never => open_sides == 2,
+        };
+        if allowed {
+            Some(step_cost * std::f64::consts::SQRT_2)
+        } else {
+            None
+        }
+    }
+
+    /// Get all the cells you can move to from `pos` in one step,
+    /// along with how much it costs.
+    fn steps<C: FnMut(ICoord) -> Option<f64>>(
+        self,
+        pos: ICoord,
+        cost: &mut C,
+    ) -> Vec<(ICoord, f64)> {
+        self.directions()
+            .iter()
+            .filter_map(|&dir| {
+                self.step_cost(pos, dir, cost)
+                    .map(|step_cost| (pos + dir, step_cost))
+            })
+            .collect()
+    }
+}
+
+/// Find the cheapest path from `start` to `goal` using A*.
+///
+/// `cost` should return the cost to move into a cell, or `None` if it can't be entered.
+/// The start cell is never checked.
+///
+/// Returns the path (including `start` and `goal`) and its total cost,
+/// or `None` if there's no way to get there.
+pub fn astar<C: FnMut(ICoord) -> Option<f64>>(
+    start: ICoord,
+    goal: ICoord,
+    movement: Movement,
+    heuristic: Heuristic,
+    cost: C,
+) -> Option<(Vec<ICoord>, f64)> {
+    search(
+        start,
+        |pos| pos == goal,
+        |pos| heuristic.distance(pos, goal),
+        movement,
+        cost,
+    )
+}
+
+/// Find the cheapest path from `start` to any cell where `is_goal` returns true,
+/// using Dijkstra's algorithm.
+///
+/// This is handy when there's more than one place you'd be happy to end up,
+/// like "the nearest exit."
+///
+/// `cost` should return the cost to move into a cell, or `None` if it can't be entered.
+/// The start cell is never checked.
+///
+/// Returns the path (including `start` and the goal it reached) and its total cost,
+/// or `None` if there's no way to get to any goal.
+pub fn dijkstra<G: FnMut(ICoord) -> bool, C: FnMut(ICoord) -> Option<f64>>(
+    start: ICoord,
+    is_goal: G,
+    movement: Movement,
+    cost: C,
+) -> Option<(Vec<ICoord>, f64)> {
+    search(start, is_goal, |_| 0.0, movement, cost)
+}
+
+/// Find the path with the fewest steps from `start` to any cell where `is_goal` returns true,
+/// using a breadth-first search.
+///
+/// `passable` should return whether a cell can be entered.
+/// The start cell is never checked.
+/// Diagonal steps count the same as orthagonal ones.
+///
+/// Returns the path (including `start` and the goal it reached),
+/// or `None` if there's no way to get to any goal.
+pub fn bfs<G: FnMut(ICoord) -> bool, P: FnMut(ICoord) -> bool>(
+    start: ICoord,
+    mut is_goal: G,
+    movement: Movement,
+    mut passable: P,
+) -> Option<Vec<ICoord>> {
+    let mut cost = |pos| if passable(pos) { Some(1.0) } else { None };
+
+    let mut came_from = AHashMap::new();
+    let mut seen = AHashSet::new();
+    seen.insert(start);
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(pos) = queue.pop_front() {
+        if is_goal(pos) {
+            return Some(reconstruct(&came_from, pos));
+        }
+        for (next, _) in movement.steps(pos, &mut cost) {
+            if seen.insert(next) {
+                came_from.insert(next, pos);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Turn a path into the list of [`Direction4`] steps that would walk it.
+///
+/// Returns `None` if any two positions in the path aren't orthagonally adjacent.
+pub fn steps4(path: &[ICoord]) -> Option<Vec<Direction4>> {
+    path.windows(2)
+        .map(|pair| Direction4::from_deltas(pair[1] - pair[0]))
+        .collect()
+}
+
+/// Turn a path into the list of [`Direction8`] steps that would walk it.
+///
+/// Returns `None` if any two positions in the path aren't adjacent.
+pub fn steps8(path: &[ICoord]) -> Option<Vec<Direction8>> {
+    path.windows(2)
+        .map(|pair| Direction8::from_deltas(pair[1] - pair[0]))
+        .collect()
+}
+
+/// A* with a pluggable goal; Dijkstra is just this with a heuristic of 0.
+fn search<G, H, C>(
+    start: ICoord,
+    mut is_goal: G,
+    mut heuristic: H,
+    movement: Movement,
+    mut cost: C,
+) -> Option<(Vec<ICoord>, f64)>
+where
+    G: FnMut(ICoord) -> bool,
+    H: FnMut(ICoord) -> f64,
+    C: FnMut(ICoord) -> Option<f64>,
+{
+    let mut came_from = AHashMap::new();
+    let mut best_cost = AHashMap::new();
+    best_cost.insert(start, 0.0);
+    let mut open = BinaryHeap::new();
+    open.push(Frontier {
+        estimate: heuristic(start),
+        cost: 0.0,
+        pos: start,
+    });
+
+    while let Some(Frontier {
+        cost: so_far, pos, ..
+    }) = open.pop()
+    {
+        if best_cost.get(&pos).is_some_and(|best| so_far > *best) {
+            // We already found a better way here; this entry is stale
+            continue;
+        }
+        if is_goal(pos) {
+            return Some((reconstruct(&came_from, pos), so_far));
+        }
+        for (next, step_cost) in movement.steps(pos, &mut cost) {
+            let next_cost = so_far + step_cost;
+            let better = match best_cost.get(&next) {
+                Some(best) => next_cost < *best,
+                None => true,
+            };
+            if better {
+                best_cost.insert(next, next_cost);
+                came_from.insert(next, pos);
+                open.push(Frontier {
+                    estimate: next_cost + heuristic(next),
+                    cost: next_cost,
+                    pos: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Walk backwards through the `came_from` map to get the path to `end`.
+fn reconstruct(came_from: &AHashMap<ICoord, ICoord>, end: ICoord) -> Vec<ICoord> {
+    let mut path = vec![end];
+    let mut cursor = end;
+    while let Some(prev) = came_from.get(&cursor) {
+        path.push(*prev);
+        cursor = *prev;
+    }
+    path.reverse();
+    path
+}
+
+/// An entry in the open set, ordered so the `BinaryHeap` pops the lowest estimate first.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Frontier {
+    pub(crate) estimate: f64,
+    pub(crate) cost: f64,
+    pub(crate) pos: ICoord,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Backwards so that lower estimates are "greater"
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            // and break ties by preferring things further along
+            .then_with(|| self.cost.total_cmp(&other.cost))
+    }
+}
+
+#[test]
+fn corner_cutting() {
+    // Get from the top-left to the bottom-right of
+    //   .#
+    //   #.
+    let open = |pos: ICoord| (pos.x == pos.y && (0..2).contains(&pos.x)).then_some(1.0);
+
+    let start = ICoord::new(0, 0);
+    let goal = ICoord::new(1, 1);
+    let find = |corners| {
+        astar(
+            start,
+            goal,
+            Movement::Eight(corners),
+            Heuristic::Octile,
+            open,
+        )
+    };
+    assert!(find(CornerCutting::Always).is_some());
+    assert!(find(CornerCutting::IfOneOpen).is_none());
+    assert!(find(CornerCutting::Never).is_none());
+    assert!(astar(start, goal, Movement::Four, Heuristic::Manhattan, open).is_none());
+
+    // With one side open, only `Never` refuses
+    let open = |pos: ICoord| {
+        (pos != ICoord::new(1, 0) && (0..2).contains(&pos.x) && (0..2).contains(&pos.y))
+            .then_some(1.0)
+    };
+    let find = |corners| {
+        astar(
+            start,
+            goal,
+            Movement::Eight(corners),
+            Heuristic::Octile,
+            open,
+        )
+    };
+    let (path, _) = find(CornerCutting::IfOneOpen).unwrap();
+    assert_eq!(path, vec![start, goal]);
+    let (path, _) = find(CornerCutting::Never).unwrap();
+    assert_eq!(path.len(), 3);
+}
+
+#[test]
+fn searches_agree() {
+    // Open field with a wall down the middle, bounded to a 10x10 box
+    let passable = |pos: ICoord| {
+        (0..10).contains(&pos.x) && (0..10).contains(&pos.y) && !(pos.x == 5 && pos.y < 8)
+    };
+    let cost = |pos| passable(pos).then_some(1.0);
+    let start = ICoord::new(1, 1);
+    let goal = ICoord::new(8, 1);
+
+    for &movement in &[Movement::Four, Movement::Eight(CornerCutting::Never)] {
+        let heuristic = match movement {
+            Movement::Four => Heuristic::Manhattan,
+            _ => Heuristic::Octile,
+        };
+        let (astar_path, astar_cost) = astar(start, goal, movement, heuristic, cost).unwrap();
+        let (dijkstra_path, dijkstra_cost) =
+            dijkstra(start, |pos| pos == goal, movement, cost).unwrap();
+        let bfs_path = bfs(start, |pos| pos == goal, movement, passable).unwrap();
+
+        assert!((astar_cost - dijkstra_cost).abs() < 1e-9);
+        assert_eq!(astar_path.len(), dijkstra_path.len());
+        assert!(bfs_path.len() <= astar_path.len());
+        assert_eq!(*astar_path.last().unwrap(), goal);
+        assert!(steps8(&astar_path).is_some());
+    }
+
+    // Unreachable
+    assert!(dijkstra(
+        start,
+        |pos| pos == ICoord::new(20, 20),
+        Movement::Four,
+        cost
+    )
+    .is_none());
+}
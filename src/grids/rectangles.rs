@@ -1,4 +1,6 @@
-use super::ICoord;
+use super::{Direction4, Direction8, ICoord, Vec2};
+
+use num_traits::Float;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -64,6 +66,319 @@ impl IRect {
             ..self
         }
     }
+
+    /// Make the smallest rectangle containing both of the given corners.
+    /// They can be in any order.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ICoord, IRect};
+    /// let rect = IRect::from_corners(ICoord::new(5, -2), ICoord::new(1, 3));
+    /// assert_eq!(rect, IRect::new(1, -2, 5, 6));
+    /// ```
+    pub fn from_corners(a: ICoord, b: ICoord) -> Self {
+        let left = a.x.min(b.x);
+        let top = a.y.min(b.y);
+        let right = a.x.max(b.x);
+        let bottom = a.y.max(b.y);
+        Self::new(
+            left,
+            top,
+            (right - left + 1) as usize,
+            (bottom - top + 1) as usize,
+        )
+    }
+
+    /// Get the top-left corner of the rect.
+    pub fn top_left(&self) -> ICoord {
+        ICoord::new(self.left, self.top)
+    }
+
+    /// Get the center of the rect.
+    ///
+    /// If the width or height is even, this rounds towards the bottom-right,
+    /// so that `IRect::centered(pos, w, h).center() == pos`.
+    pub fn center(&self) -> ICoord {
+        ICoord::new(
+            self.left + self.width as isize / 2,
+            self.top + self.height as isize / 2,
+        )
+    }
+
+    /// Get a point on the boundary of the rect.
+    ///
+    /// Diagonal directions give corners, and orthagonal directions give the
+    /// middle of that edge (rounded the same way as [`IRect::center`]).
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Direction8, ICoord, IRect};
+    /// let rect = IRect::new(0, 0, 5, 3);
+    /// assert_eq!(rect.anchor(Direction8::NorthWest), ICoord::new(0, 0));
+    /// assert_eq!(rect.anchor(Direction8::SouthEast), ICoord::new(4, 2));
+    /// assert_eq!(rect.anchor(Direction8::North), ICoord::new(2, 0));
+    /// assert_eq!(rect.anchor(Direction8::West), ICoord::new(0, 1));
+    /// ```
+    pub fn anchor(&self, dir: Direction8) -> ICoord {
+        let center = self.center();
+        let deltas = dir.deltas();
+        let x = match deltas.x {
+            -1 => self.left,
+            0 => center.x,
+            _ => self.right(),
+        };
+        let y = match deltas.y {
+            -1 => self.top,
+            0 => center.y,
+            _ => self.bottom(),
+        };
+        ICoord::new(x, y)
+    }
+
+    /// Get the 1-thick strip of cells along one edge of the rect.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{Direction4, IRect};
+    /// let rect = IRect::new(0, 0, 5, 3);
+    /// assert_eq!(rect.edge(Direction4::East), IRect::new(4, 0, 1, 3));
+    /// assert_eq!(rect.edge(Direction4::North), IRect::new(0, 0, 5, 1));
+    /// ```
+    pub fn edge(&self, side: Direction4) -> IRect {
+        let thickness = |len: usize| len.min(1);
+        match side {
+            Direction4::North => {
+                IRect::new(self.left, self.top, self.width, thickness(self.height))
+            }
+            Direction4::South => IRect::new(
+                self.left,
+                self.bottom().max(self.top),
+                self.width,
+                thickness(self.height),
+            ),
+            Direction4::West => IRect::new(self.left, self.top, thickness(self.width), self.height),
+            Direction4::East => IRect::new(
+                self.right().max(self.left),
+                self.top,
+                thickness(self.width),
+                self.height,
+            ),
+        }
+    }
+
+    /// Do these two rects share any cells?
+    pub fn intersects(&self, other: IRect) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Get the rect of cells shared by both rects, or `None` if they don't overlap.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::IRect;
+    /// let a = IRect::new(0, 0, 4, 4);
+    /// let b = IRect::new(2, -1, 4, 2);
+    /// assert_eq!(a.intersection(b), Some(IRect::new(2, 0, 2, 1)));
+    /// assert_eq!(a.intersection(IRect::new(4, 0, 1, 1)), None);
+    /// ```
+    pub fn intersection(&self, other: IRect) -> Option<IRect> {
+        let left = self.left.max(other.left);
+        let top = self.top.max(other.top);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right >= left && bottom >= top {
+            Some(IRect::from_corners(
+                ICoord::new(left, top),
+                ICoord::new(right, bottom),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Get the smallest rect containing both rects.
+    ///
+    /// Empty rects (with a width or height of 0) are ignored.
+    pub fn union(&self, other: IRect) -> IRect {
+        if other.area() == 0 {
+            *self
+        } else if self.area() == 0 {
+            other
+        } else {
+            IRect::from_corners(
+                ICoord::new(self.left.min(other.left), self.top.min(other.top)),
+                ICoord::new(
+                    self.right().max(other.right()),
+                    self.bottom().max(other.bottom()),
+                ),
+            )
+        }
+    }
+
+    /// Is the other rect completely inside this one?
+    ///
+    /// Edges touching counts.
+    pub fn contains_rect(&self, other: IRect) -> bool {
+        self.left <= other.left
+            && self.top <= other.top
+            && self.right() >= other.right()
+            && self.bottom() >= other.bottom()
+    }
+
+    /// Grow the rect by `margin` cells on every side.
+    pub fn expand(self, margin: usize) -> IRect {
+        let margin = margin as isize;
+        self.expand_sides(margin, margin, margin, margin)
+    }
+
+    /// Shrink the rect by `margin` cells on every side.
+    ///
+    /// If it shrinks past nothing, the width or height will be 0.
+    pub fn shrink(self, margin: usize) -> IRect {
+        let margin = -(margin as isize);
+        self.expand_sides(margin, margin, margin, margin)
+    }
+
+    /// Move each side of the rect outwards by the given amounts.
+    /// Negative amounts move the side inwards.
+    ///
+    /// If it shrinks past nothing, the width or height will be 0.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::IRect;
+    /// let rect = IRect::new(0, 0, 10, 10);
+    /// assert_eq!(rect.expand_sides(1, 0, -2, 3), IRect::new(-1, 0, 9, 13));
+    /// assert_eq!(rect.shrink(2), IRect::new(2, 2, 6, 6));
+    /// assert_eq!(rect.shrink(6).area(), 0);
+    /// ```
+    pub fn expand_sides(self, left: isize, top: isize, right: isize, bottom: isize) -> IRect {
+        let width = (self.width as isize + left + right).max(0);
+        let height = (self.height as isize + top + bottom).max(0);
+        IRect::new(
+            self.left - left,
+            self.top - top,
+            width as usize,
+            height as usize,
+        )
+    }
+
+    /// Get the point in the rect closest to the given point.
+    ///
+    /// If the rect is empty, this returns the top-left corner.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ICoord, IRect};
+    /// let rect = IRect::new(0, 0, 10, 5);
+    /// assert_eq!(rect.clamp_point(ICoord::new(-4, 3)), ICoord::new(0, 3));
+    /// assert_eq!(rect.clamp_point(ICoord::new(12, 12)), ICoord::new(9, 4));
+    /// ```
+    pub fn clamp_point(&self, pos: ICoord) -> ICoord {
+        ICoord::new(
+            pos.x.min(self.right()).max(self.left),
+            pos.y.min(self.bottom()).max(self.top),
+        )
+    }
+
+    /// Cut the rect into a left part `offset` cells wide and a right part with the rest.
+    ///
+    /// If `offset` is larger than the width, the right part is empty.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::IRect;
+    /// let (left, right) = IRect::new(0, 0, 10, 5).split_horizontal(3);
+    /// assert_eq!(left, IRect::new(0, 0, 3, 5));
+    /// assert_eq!(right, IRect::new(3, 0, 7, 5));
+    /// ```
+    pub fn split_horizontal(self, offset: usize) -> (IRect, IRect) {
+        let offset = offset.min(self.width);
+        (
+            IRect::new(self.left, self.top, offset, self.height),
+            IRect::new(
+                self.left + offset as isize,
+                self.top,
+                self.width - offset,
+                self.height,
+            ),
+        )
+    }
+
+    /// Cut the rect into a top part `offset` cells tall and a bottom part with the rest.
+    ///
+    /// If `offset` is larger than the height, the bottom part is empty.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::IRect;
+    /// let (top, bottom) = IRect::new(0, 0, 10, 5).split_vertical(1);
+    /// assert_eq!(top, IRect::new(0, 0, 10, 1));
+    /// assert_eq!(bottom, IRect::new(0, 1, 10, 4));
+    /// ```
+    pub fn split_vertical(self, offset: usize) -> (IRect, IRect) {
+        let offset = offset.min(self.height);
+        (
+            IRect::new(self.left, self.top, self.width, offset),
+            IRect::new(
+                self.left,
+                self.top + offset as isize,
+                self.width,
+                self.height - offset,
+            ),
+        )
+    }
+
+    /// Get the parts of this rect that aren't in the other rect, as up to four rects.
+    ///
+    /// The pieces are the full-width strips above and below the other rect,
+    /// then the parts to its left and right. Empty pieces are skipped.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::IRect;
+    /// let frame = IRect::new(0, 0, 5, 5).subtract(IRect::new(1, 1, 3, 3));
+    /// assert_eq!(
+    ///     frame,
+    ///     vec![
+    ///         IRect::new(0, 0, 5, 1),
+    ///         IRect::new(0, 4, 5, 1),
+    ///         IRect::new(0, 1, 1, 3),
+    ///         IRect::new(4, 1, 1, 3),
+    ///     ]
+    /// );
+    ///
+    /// let notch = IRect::new(0, 0, 5, 5).subtract(IRect::new(3, -2, 10, 4));
+    /// assert_eq!(notch, vec![IRect::new(0, 2, 5, 3), IRect::new(0, 0, 3, 2)]);
+    /// ```
+    pub fn subtract(&self, other: IRect) -> Vec<IRect> {
+        let cut = match self.intersection(other) {
+            Some(it) => it,
+            None => return vec![*self],
+        };
+        let pieces = [
+            IRect::new(
+                self.left,
+                self.top,
+                self.width,
+                (cut.top - self.top) as usize,
+            ),
+            IRect::new(
+                self.left,
+                cut.bottom() + 1,
+                self.width,
+                (self.bottom() - cut.bottom()) as usize,
+            ),
+            IRect::new(
+                self.left,
+                cut.top,
+                (cut.left - self.left) as usize,
+                cut.height,
+            ),
+            IRect::new(
+                cut.right() + 1,
+                cut.top,
+                (self.right() - cut.right()) as usize,
+                cut.height,
+            ),
+        ];
+        pieces
+            .iter()
+            .filter(|piece| piece.area() != 0)
+            .copied()
+            .collect()
+    }
 }
 
 impl std::ops::Add<ICoord> for IRect {
@@ -73,6 +388,176 @@ impl std::ops::Add<ICoord> for IRect {
     }
 }
 
+/// A rectangle with floating-point values.
+///
+/// Unlike [`IRect`], which is a set of cells, this is a continuous area:
+/// it goes from `left` to `left + width`, and from `top` to `top + height`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FRect<F: Float> {
+    pub left: F,
+    pub top: F,
+    pub width: F,
+    pub height: F,
+}
+
+impl<F: Float> FRect<F> {
+    pub fn new(left: F, top: F, width: F, height: F) -> Self {
+        Self {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    /// Make the smallest rectangle with both points on its boundary.
+    /// They can be in any order.
+    pub fn from_corners(a: Vec2<F>, b: Vec2<F>) -> Self {
+        let left = a.x.min(b.x);
+        let top = a.y.min(b.y);
+        Self::new(left, top, a.x.max(b.x) - left, a.y.max(b.y) - top)
+    }
+
+    /// Return a new rectangle centered at the given position with the given w/h
+    pub fn centered(center: Vec2<F>, width: F, height: F) -> Self {
+        let two = F::one() + F::one();
+        Self::new(
+            center.x - width / two,
+            center.y - height / two,
+            width,
+            height,
+        )
+    }
+
+    pub fn right(&self) -> F {
+        self.left + self.width
+    }
+
+    pub fn bottom(&self) -> F {
+        self.top + self.height
+    }
+
+    pub fn top_left(&self) -> Vec2<F> {
+        Vec2::new(self.left, self.top)
+    }
+
+    pub fn size(&self) -> Vec2<F> {
+        Vec2::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Vec2<F> {
+        let two = F::one() + F::one();
+        Vec2::new(self.left + self.width / two, self.top + self.height / two)
+    }
+
+    pub fn area(&self) -> F {
+        self.width * self.height
+    }
+
+    /// Does this rect contain the pos?
+    ///
+    /// Points on the boundary count.
+    pub fn contains(&self, pos: Vec2<F>) -> bool {
+        self.left <= pos.x && pos.x <= self.right() && self.top <= pos.y && pos.y <= self.bottom()
+    }
+
+    /// Do these two rects overlap?
+    ///
+    /// Rects that only touch along an edge don't count.
+    pub fn intersects(&self, other: FRect<F>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Get the area shared by both rects, or `None` if they don't overlap.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::FRect;
+    /// let a = FRect::new(0.0, 0.0, 4.0, 4.0);
+    /// let b = FRect::new(3.0, -1.0, 4.0, 2.0);
+    /// assert_eq!(a.intersection(b), Some(FRect::new(3.0, 0.0, 1.0, 1.0)));
+    /// assert_eq!(a.intersection(FRect::new(4.0, 0.0, 1.0, 1.0)), None);
+    /// ```
+    pub fn intersection(&self, other: FRect<F>) -> Option<FRect<F>> {
+        let left = self.left.max(other.left);
+        let top = self.top.max(other.top);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right > left && bottom > top {
+            Some(FRect::new(left, top, right - left, bottom - top))
+        } else {
+            None
+        }
+    }
+
+    /// Get the smallest rect containing both rects.
+    pub fn union(&self, other: FRect<F>) -> FRect<F> {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        FRect::new(
+            left,
+            top,
+            self.right().max(other.right()) - left,
+            self.bottom().max(other.bottom()) - top,
+        )
+    }
+
+    pub fn shifted(self, by: Vec2<F>) -> FRect<F> {
+        FRect {
+            left: self.left + by.x,
+            top: self.top + by.y,
+            ..self
+        }
+    }
+
+    /// Grow the rect by `margin` on every side. Negative margins shrink it.
+    ///
+    /// If it shrinks past nothing, the width or height will be 0.
+    pub fn expand(self, margin: F) -> FRect<F> {
+        let two = F::one() + F::one();
+        FRect::new(
+            self.left - margin,
+            self.top - margin,
+            (self.width + margin * two).max(F::zero()),
+            (self.height + margin * two).max(F::zero()),
+        )
+    }
+
+    /// Get the point in the rect closest to the given point.
+    pub fn clamp_point(&self, pos: Vec2<F>) -> Vec2<F> {
+        Vec2::new(
+            pos.x.min(self.right()).max(self.left),
+            pos.y.min(self.bottom()).max(self.top),
+        )
+    }
+}
+
+/// Get the area covered by all the cells in the IRect.
+///
+/// ```
+/// # use cogs_gamedev::grids::{FRect, IRect};
+/// let rect: FRect<f32> = IRect::new(-1, 2, 3, 4).into();
+/// assert_eq!(rect, FRect::new(-1.0, 2.0, 3.0, 4.0));
+/// ```
+impl<F: Float> From<IRect> for FRect<F> {
+    fn from(value: IRect) -> Self {
+        let corner = Vec2::<F>::from(value.top_left());
+        FRect::new(
+            corner.x,
+            corner.y,
+            F::from(value.width).unwrap_or_else(F::nan),
+            F::from(value.height).unwrap_or_else(F::nan),
+        )
+    }
+}
+
+impl<F: Float> std::ops::Add<Vec2<F>> for FRect<F> {
+    type Output = FRect<F>;
+    fn add(self, rhs: Vec2<F>) -> Self::Output {
+        self.shifted(rhs)
+    }
+}
+
 pub struct RectIter {
     rect: IRect,
     cursor: ICoord,
@@ -113,3 +598,28 @@ impl Iterator for RectIter {
         }
     }
 }
+
+#[test]
+fn subtract_covers_everything() {
+    let base = IRect::new(-3, 2, 9, 7);
+    for other in [
+        IRect::new(0, 0, 100, 100),
+        IRect::new(-5, 4, 4, 1),
+        IRect::new(0, 3, 2, 2),
+        IRect::new(20, 20, 2, 2),
+        base,
+    ]
+    .iter()
+    {
+        let pieces = base.subtract(*other);
+        for pos in base.contained_coords() {
+            let in_pieces = pieces.iter().filter(|p| p.contains(pos)).count();
+            let expected = if other.contains(pos) { 0 } else { 1 };
+            assert_eq!(in_pieces, expected, "{:?} at {}", other, pos);
+        }
+        for piece in pieces.iter() {
+            assert!(base.contains_rect(*piece));
+            assert!(!piece.intersects(*other));
+        }
+    }
+}
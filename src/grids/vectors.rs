@@ -0,0 +1,306 @@
+//! Floating-point vectors.
+
+use super::{Direction4, Direction8, ICoord};
+use crate::ease::Interpolator;
+
+use num_traits::{Float, FloatConst};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// A 2D vector of floats.
+///
+/// Like everything else in `grids`, +Y is downwards, so positive angles go *clockwise*.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Vec2<F: Float> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: Float> Vec2<F> {
+    /// Make a new vector.
+    pub fn new(x: F, y: F) -> Self {
+        Self { x, y }
+    }
+
+    /// The zero vector.
+    pub fn zero() -> Self {
+        Self::new(F::zero(), F::zero())
+    }
+
+    /// Make a unit vector pointing at the given angle.
+    ///
+    /// This uses the same standard as [`Direction4::radians`]: 0 radians is to the right,
+    /// and positive radians increment *clockwise*.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Vec2;
+    /// use std::f32::consts::TAU;
+    ///
+    /// let down = Vec2::from_angle(TAU / 4.0);
+    /// assert!((down - Vec2::new(0.0, 1.0)).length() < 1e-6);
+    /// ```
+    pub fn from_angle(radians: F) -> Self {
+        Self::new(radians.cos(), radians.sin())
+    }
+
+    /// Get the angle this vector points at, in radians.
+    ///
+    /// See [`Vec2::from_angle`] for which way is which.
+    /// The output is between -PI and PI.
+    pub fn angle(self) -> F {
+        self.y.atan2(self.x)
+    }
+
+    /// Get the angle between this and another vector, in radians.
+    ///
+    /// This is always positive, between 0 and PI.
+    pub fn angle_between(self, other: Self) -> F {
+        self.cross(other).atan2(self.dot(other)).abs()
+    }
+
+    /// Get the length of this vector.
+    pub fn length(self) -> F {
+        self.x.hypot(self.y)
+    }
+
+    /// Get the square of the length of this vector.
+    /// This is faster than [`Vec2::length`], so it's good for comparisons.
+    pub fn length_squared(self) -> F {
+        self.dot(self)
+    }
+
+    /// Get the distance between this and another vector.
+    pub fn distance(self, other: Self) -> F {
+        (self - other).length()
+    }
+
+    /// Get a vector pointing the same way as this one with a length of 1.
+    ///
+    /// The zero vector stays zero.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Vec2;
+    /// assert_eq!(Vec2::new(3.0, -4.0).normalize(), Vec2::new(0.6, -0.8));
+    /// assert_eq!(Vec2::<f64>::zero().normalize(), Vec2::zero());
+    /// ```
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length.is_zero() {
+            self
+        } else {
+            self / length
+        }
+    }
+
+    /// Get the dot product of this and another vector.
+    pub fn dot(self, other: Self) -> F {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Get the z-component of the cross product of this and another vector.
+    ///
+    /// Because +Y is downwards, this is positive when `other` is clockwise of `self`.
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::Vec2;
+    /// let right = Vec2::new(1.0, 0.0);
+    /// let down = Vec2::new(0.0, 1.0);
+    /// assert_eq!(right.cross(down), 1.0);
+    /// assert_eq!(down.cross(right), -1.0);
+    /// ```
+    pub fn cross(self, other: Self) -> F {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Rotate this vector clockwise by the given angle in radians.
+    pub fn rotate(self, radians: F) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Get the vector at a right angle to this one, rotated clockwise.
+    pub fn perpendicular(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// Convert this to an ICoord, rounding each component down.
+    ///
+    /// Like an `as` cast, components too big or small to fit in an `isize` saturate
+    /// to `isize::MAX` or `isize::MIN`, and NaN becomes 0.
+    /// (So check for NaN yourself if that would be a problem.)
+    ///
+    /// ```
+    /// # use cogs_gamedev::grids::{ICoord, Vec2};
+    /// let v = Vec2::new(1.5, -2.5);
+    /// assert_eq!(v.to_icoord_floor(), ICoord::new(1, -3));
+    /// assert_eq!(v.to_icoord_ceil(), ICoord::new(2, -2));
+    /// // Rounds half away from 0
+    /// assert_eq!(v.to_icoord_round(), ICoord::new(2, -3));
+    ///
+    /// let weird = Vec2::new(f32::INFINITY, f32::NAN);
+    /// assert_eq!(weird.to_icoord_floor(), ICoord::new(isize::MAX, 0));
+    /// ```
+    pub fn to_icoord_floor(self) -> ICoord {
+        self.map_to_icoord(F::floor)
+    }
+
+    /// Convert this to an ICoord, rounding each component to the nearest integer.
+    /// Halfway cases round away from 0.
+    ///
+    /// Out-of-range components are handled like in [`Vec2::to_icoord_floor`].
+    pub fn to_icoord_round(self) -> ICoord {
+        self.map_to_icoord(F::round)
+    }
+
+    /// Convert this to an ICoord, rounding each component up.
+    ///
+    /// Out-of-range components are handled like in [`Vec2::to_icoord_floor`].
+    pub fn to_icoord_ceil(self) -> ICoord {
+        self.map_to_icoord(F::ceil)
+    }
+
+    fn map_to_icoord(self, f: fn(F) -> F) -> ICoord {
+        let convert = |component: F| {
+            let rounded = f(component);
+            rounded.to_isize().unwrap_or(if rounded.is_nan() {
+                0
+            } else if rounded > F::zero() {
+                isize::MAX
+            } else {
+                isize::MIN
+            })
+        };
+        ICoord::new(convert(self.x), convert(self.y))
+    }
+}
+
+impl<F: Float> Add for Vec2<F> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<F: Float> AddAssign for Vec2<F> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<F: Float> Sub for Vec2<F> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<F: Float> SubAssign for Vec2<F> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl<F: Float> Mul<F> for Vec2<F> {
+    type Output = Self;
+    fn mul(self, rhs: F) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<F: Float> MulAssign<F> for Vec2<F> {
+    fn mul_assign(&mut self, rhs: F) {
+        *self = *self * rhs;
+    }
+}
+
+impl<F: Float> Div<F> for Vec2<F> {
+    type Output = Self;
+    fn div(self, rhs: F) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs)
+    }
+}
+
+impl<F: Float> DivAssign<F> for Vec2<F> {
+    fn div_assign(&mut self, rhs: F) {
+        *self = *self / rhs;
+    }
+}
+
+impl<F: Float> Neg for Vec2<F> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+/// Linearly interpolate between two vectors, which means all the easing functions
+/// work on them too.
+///
+/// ```
+/// # use cogs_gamedev::grids::Vec2;
+/// use cogs_gamedev::ease::Interpolator;
+///
+/// let start = Vec2::new(0.0, 10.0);
+/// let end = Vec2::new(4.0, 2.0);
+/// assert_eq!(0.5.lerp(start, end), Vec2::new(2.0, 6.0));
+/// assert_eq!(0.5.quad_in(start, end), Vec2::new(1.0, 8.0));
+/// ```
+impl<F: Float + FloatConst> Interpolator<Vec2<F>> for F {
+    fn lerp(self, start: Vec2<F>, end: Vec2<F>) -> Vec2<F> {
+        Vec2::new(self.lerp(start.x, end.x), self.lerp(start.y, end.y))
+    }
+}
+
+impl<F: Float> From<ICoord> for Vec2<F> {
+    fn from(value: ICoord) -> Self {
+        Self::new(
+            F::from(value.x).unwrap_or_else(F::nan),
+            F::from(value.y).unwrap_or_else(F::nan),
+        )
+    }
+}
+
+/// Get a unit vector pointing in this direction.
+impl<F: Float> From<Direction4> for Vec2<F> {
+    fn from(value: Direction4) -> Self {
+        Self::from(value.deltas())
+    }
+}
+
+/// Get a unit vector pointing in this direction.
+///
+/// ```
+/// # use cogs_gamedev::grids::{Direction8, Vec2};
+/// let v: Vec2<f32> = Direction8::SouthEast.into();
+/// assert!((v.length() - 1.0).abs() < 1e-6);
+/// assert!((v.angle() - Direction8::SouthEast.radians()).abs() < 1e-6);
+/// ```
+impl<F: Float> From<Direction8> for Vec2<F> {
+    fn from(value: Direction8) -> Self {
+        Self::from(value.deltas()).normalize()
+    }
+}
+
+impl<F: Float> From<[F; 2]> for Vec2<F> {
+    fn from(value: [F; 2]) -> Self {
+        Self::new(value[0], value[1])
+    }
+}
+
+impl<F: Float> From<Vec2<F>> for [F; 2] {
+    fn from(value: Vec2<F>) -> Self {
+        [value.x, value.y]
+    }
+}
+
+impl<F: Float + Display> Display for Vec2<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
@@ -0,0 +1,809 @@
+/*!
+[Wave Function Collapse](https://github.com/mxgmn/WaveFunctionCollapse), for generating maps
+and textures that locally look like something you give it.
+
+There are two ways to tell it what things should look like:
+
+- A [`TiledModel`], where you list out tiles and which tiles are allowed next to each other.
+- An [`OverlappingModel`], where you give it a sample grid and it figures out the rules itself.
+
+Either way, the model gives you a [`Solver`], which you can pin cells of before
+running it. The solver works on pattern indices, so you turn its output back into tiles
+with the model's `render` method.
+
+If the solver paints itself into a corner, it starts over from scratch (keeping any pins)
+up to a number of times you choose.
+*/
+
+use super::{Coord, Direction4, Grid, ICoord};
+use crate::chance::WeightedPicker;
+
+use ahash::{AHashMap, AHashSet};
+use rand::Rng;
+
+use std::{fmt::Display, hash::Hash};
+
+/// The solver got into a state where a cell had no possibilities left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Contradiction {
+    /// The cell that ran out of possibilities.
+    pub pos: Coord,
+}
+
+impl Display for Contradiction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "contradiction at ({}, {}): no possibilities left",
+            self.pos.x, self.pos.y
+        )
+    }
+}
+
+impl std::error::Error for Contradiction {}
+
+/**
+Solves a wave function collapse problem on a grid of pattern indices.
+
+You get one of these from [`TiledModel::solver`] or [`OverlappingModel::solver`].
+
+Each step, the cell with the fewest options left (by Shannon entropy) is collapsed to
+one pattern, picked with a [`WeightedPicker`] using the patterns' weights. Then
+the choice is propagated to the rest of the grid to rule out anything that's no longer possible.
+*/
+#[derive(Debug, Clone)]
+pub struct Solver {
+    width: usize,
+    height: usize,
+    periodic: bool,
+    weights: Vec<f64>,
+    /// `propagator[dir][a]` lists the patterns allowed one step in `dir` from `a`.
+    propagator: [Vec<Vec<usize>>; 4],
+    /// `initial_support[b][dir]` is how many patterns allow `b` one step in `dir` from them.
+    initial_support: Vec<[usize; 4]>,
+    /// Cell indices and whether each pattern is allowed there.
+    pins: Vec<(usize, Vec<bool>)>,
+
+    // Everything below here gets reset each attempt.
+    /// Indexed by `cell * pattern_count + pattern`
+    possible: Vec<bool>,
+    /// Indexed by `cell * pattern_count + pattern`.
+    /// `support[..][dir]` is how many patterns still possible in the cell one step opposite `dir`
+    /// allow this pattern.
+    support: Vec<[usize; 4]>,
+    remaining: Vec<usize>,
+    sum_weights: Vec<f64>,
+    sum_weight_logs: Vec<f64>,
+    /// Bans that haven't been propagated yet
+    removed: Vec<(usize, usize)>,
+}
+
+impl Solver {
+    fn new(
+        width: usize,
+        height: usize,
+        periodic: bool,
+        weights: Vec<f64>,
+        propagator: [Vec<Vec<usize>>; 4],
+    ) -> Self {
+        assert!(!weights.is_empty(), "Cannot solve with no patterns!");
+
+        let mut initial_support = vec![[0; 4]; weights.len()];
+        for dir in Direction4::DIRECTIONS.iter() {
+            for allowed in propagator[*dir as usize].iter() {
+                for &b in allowed.iter() {
+                    initial_support[b][*dir as usize] += 1;
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            periodic,
+            weights,
+            propagator,
+            initial_support,
+            pins: Vec::new(),
+            possible: Vec::new(),
+            support: Vec::new(),
+            remaining: Vec::new(),
+            sum_weights: Vec::new(),
+            sum_weight_logs: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the grid wraps around at the edges.
+    pub fn is_periodic(&self) -> bool {
+        self.periodic
+    }
+
+    /// How many different patterns there are.
+    pub fn pattern_count(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Force the cell at the given position to be the given pattern.
+    ///
+    /// Panics if the position is out of bounds.
+    pub fn pin(&mut self, pos: Coord, pattern: usize) {
+        self.restrict(pos, |p| p == pattern);
+    }
+
+    /// Only allow patterns at the given position that the predicate returns `true` for.
+    ///
+    /// Restricting the same cell multiple times only allows patterns all the predicates allowed.
+    ///
+    /// Panics if the position is out of bounds.
+    pub fn restrict<F: FnMut(usize) -> bool>(&mut self, pos: Coord, allowed: F) {
+        assert!(
+            pos.x < self.width && pos.y < self.height,
+            "{} is out of bounds of a {}x{} solver",
+            pos,
+            self.width,
+            self.height
+        );
+        let allowed = (0..self.pattern_count()).map(allowed).collect();
+        self.pins.push((pos.to_2d_idx(self.width), allowed));
+    }
+
+    /// Remove all pins and restrictions.
+    pub fn clear_pins(&mut self) {
+        self.pins.clear();
+    }
+
+    /// Try to solve the grid, starting over if there's a contradiction, up to `max_attempts` times.
+    ///
+    /// Returns a grid of pattern indices, or the last contradiction it ran into.
+    /// If the pins contradict each other, this gives up immediately.
+    pub fn solve<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        max_attempts: usize,
+    ) -> Result<Grid<usize>, Contradiction> {
+        let mut last_err = Contradiction {
+            pos: Coord::new(0, 0),
+        };
+        for _ in 0..max_attempts {
+            // Nothing random has happened yet, so a failure here will happen every time
+            self.reset()?;
+            match self.run(rng) {
+                Ok(()) => {
+                    let count = self.pattern_count();
+                    return Ok(Grid::from_fn(self.width, self.height, |pos| {
+                        let cell = pos.to_2d_idx(self.width);
+                        (0..count)
+                            .find(|&p| self.possible[cell * count + p])
+                            .unwrap()
+                    }));
+                }
+                Err(err) => last_err = err,
+            }
+        }
+        Err(last_err)
+    }
+
+    fn reset(&mut self) -> Result<(), Contradiction> {
+        let cells = self.width * self.height;
+        let count = self.pattern_count();
+        let sum_weights: f64 = self.weights.iter().sum();
+        let sum_weight_logs: f64 = self.weights.iter().map(|w| w * w.ln()).sum();
+
+        self.possible = vec![true; cells * count];
+        self.support = (0..cells)
+            .flat_map(|_| self.initial_support.iter().copied())
+            .collect();
+        self.remaining = vec![count; cells];
+        self.sum_weights = vec![sum_weights; cells];
+        self.sum_weight_logs = vec![sum_weight_logs; cells];
+        self.removed.clear();
+
+        // Some patterns might not be allowed next to anything
+        for cell in 0..cells {
+            for dir in Direction4::DIRECTIONS.iter() {
+                if self.neighbor(cell, dir.flip()).is_none() {
+                    continue;
+                }
+                for pattern in 0..count {
+                    if self.initial_support[pattern][*dir as usize] == 0
+                        && self.possible[cell * count + pattern]
+                    {
+                        self.ban(cell, pattern)?;
+                    }
+                }
+            }
+        }
+
+        for idx in 0..self.pins.len() {
+            let cell = self.pins[idx].0;
+            for pattern in 0..count {
+                if !self.pins[idx].1[pattern] && self.possible[cell * count + pattern] {
+                    self.ban(cell, pattern)?;
+                }
+            }
+        }
+        self.propagate()
+    }
+
+    fn run<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<(), Contradiction> {
+        let count = self.pattern_count();
+        loop {
+            // Find the cell with the least entropy, with a little noise to break ties
+            let mut best = None;
+            for cell in 0..self.remaining.len() {
+                if self.remaining[cell] <= 1 {
+                    continue;
+                }
+                let entropy = self.sum_weights[cell].ln()
+                    - self.sum_weight_logs[cell] / self.sum_weights[cell]
+                    + rng.gen::<f64>() * 1e-6;
+                let lower = match best {
+                    Some((_, best_entropy)) => entropy < best_entropy,
+                    None => true,
+                };
+                if lower {
+                    best = Some((cell, entropy));
+                }
+            }
+            let cell = match best {
+                Some((cell, _)) => cell,
+                None => return Ok(()),
+            };
+
+            let options = (0..count)
+                .filter(|&p| self.possible[cell * count + p])
+                .map(|p| (p, self.weights[p]))
+                .collect();
+            let chosen = WeightedPicker::pick(options, rng);
+            for pattern in 0..count {
+                if pattern != chosen && self.possible[cell * count + pattern] {
+                    self.ban(cell, pattern)?;
+                }
+            }
+            self.propagate()?;
+        }
+    }
+
+    fn ban(&mut self, cell: usize, pattern: usize) -> Result<(), Contradiction> {
+        let count = self.pattern_count();
+        let weight = self.weights[pattern];
+        self.possible[cell * count + pattern] = false;
+        self.remaining[cell] -= 1;
+        self.sum_weights[cell] -= weight;
+        self.sum_weight_logs[cell] -= weight * weight.ln();
+        self.removed.push((cell, pattern));
+
+        if self.remaining[cell] == 0 {
+            Err(Contradiction {
+                pos: Coord::new(cell % self.width, cell / self.width),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn propagate(&mut self) -> Result<(), Contradiction> {
+        let count = self.pattern_count();
+        while let Some((cell, pattern)) = self.removed.pop() {
+            for dir in Direction4::DIRECTIONS.iter() {
+                let neighbor = match self.neighbor(cell, *dir) {
+                    Some(it) => it,
+                    None => continue,
+                };
+                let d = *dir as usize;
+                for idx in 0..self.propagator[d][pattern].len() {
+                    let other = self.propagator[d][pattern][idx];
+                    let support = &mut self.support[neighbor * count + other][d];
+                    *support -= 1;
+                    if *support == 0 && self.possible[neighbor * count + other] {
+                        self.ban(neighbor, other)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn neighbor(&self, cell: usize, dir: Direction4) -> Option<usize> {
+        let pos = ICoord::new((cell % self.width) as isize, (cell / self.width) as isize);
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut pos = pos + dir.deltas();
+        if self.periodic {
+            pos = ICoord::new(pos.x.rem_euclid(width), pos.y.rem_euclid(height));
+        } else if pos.x < 0 || pos.y < 0 || pos.x >= width || pos.y >= height {
+            return None;
+        }
+        Some(pos.y as usize * self.width + pos.x as usize)
+    }
+}
+
+/**
+The simple tiled model: you list out the tiles and which ones can go next to each other.
+
+```
+# use cogs_gamedev::grids::{Coord, Direction4, wfc::TiledModel};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terrain { Land, Coast, Sea }
+
+let mut model = TiledModel::new();
+let land = model.add_tile(Terrain::Land, 3.0);
+let coast = model.add_tile(Terrain::Coast, 1.0);
+let sea = model.add_tile(Terrain::Sea, 3.0);
+for dir in Direction4::DIRECTIONS.iter() {
+    model.allow(land, *dir, land);
+    model.allow(land, *dir, coast);
+    model.allow(coast, *dir, coast);
+    model.allow(coast, *dir, sea);
+    model.allow(sea, *dir, sea);
+}
+
+let mut solver = model.solver(20, 10, false);
+solver.pin(Coord::new(0, 0), land);
+solver.pin(Coord::new(19, 9), sea);
+let map = model.render(&solver.solve(&mut StdRng::seed_from_u64(0), 10).unwrap());
+
+assert_eq!(map[Coord::new(0, 0)], Terrain::Land);
+assert_eq!(map[Coord::new(19, 9)], Terrain::Sea);
+// Land never touches sea
+for (pos, tile) in map.iter_coords() {
+    if *tile == Terrain::Land {
+        for neighbor in pos.to_icoord().neighbors4().iter() {
+            assert_ne!(map.get(*neighbor), Some(&Terrain::Sea));
+        }
+    }
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct TiledModel<T> {
+    tiles: Vec<T>,
+    weights: Vec<f64>,
+    /// `(a, dir, b)` means `b` is allowed one step in `dir` from `a`.
+    rules: AHashSet<(usize, Direction4, usize)>,
+}
+
+impl<T> TiledModel<T> {
+    /// Make a new model with no tiles.
+    pub fn new() -> Self {
+        Self {
+            tiles: Vec::new(),
+            weights: Vec::new(),
+            rules: AHashSet::new(),
+        }
+    }
+
+    /// Add a tile, returning its index.
+    ///
+    /// Tiles with higher weights show up more often.
+    ///
+    /// Panics if the weight isn't positive and finite.
+    pub fn add_tile(&mut self, tile: T, weight: f64) -> usize {
+        assert!(
+            weight > 0.0 && weight.is_finite(),
+            "Tile weights must be positive and finite, not {}",
+            weight
+        );
+        self.tiles.push(tile);
+        self.weights.push(weight);
+        self.tiles.len() - 1
+    }
+
+    /// Allow tile `b` to go one step in `dir` from tile `a`.
+    ///
+    /// This also allows `a` to go one step in the opposite direction from `b`.
+    pub fn allow(&mut self, a: usize, dir: Direction4, b: usize) {
+        self.rules.insert((a, dir, b));
+        self.rules.insert((b, dir.flip(), a));
+    }
+
+    /// Check if tile `b` is allowed one step in `dir` from tile `a`.
+    pub fn is_allowed(&self, a: usize, dir: Direction4, b: usize) -> bool {
+        self.rules.contains(&(a, dir, b))
+    }
+
+    /// Get all the tiles, in index order.
+    pub fn tiles(&self) -> &[T] {
+        &self.tiles
+    }
+
+    /// Get the index of a tile.
+    pub fn find(&self, tile: &T) -> Option<usize>
+    where
+        T: PartialEq,
+    {
+        self.tiles.iter().position(|it| it == tile)
+    }
+
+    /// Make a solver for an area of the given size.
+    ///
+    /// If `periodic` is set, the edges of the area wrap around.
+    ///
+    /// Panics if there are no tiles.
+    pub fn solver(&self, width: usize, height: usize, periodic: bool) -> Solver {
+        let mut propagator: [Vec<Vec<usize>>; 4] = Default::default();
+        for allowed in propagator.iter_mut() {
+            *allowed = vec![Vec::new(); self.tiles.len()];
+        }
+        for &(a, dir, b) in self.rules.iter() {
+            propagator[dir as usize][a].push(b);
+        }
+        // Hash sets don't iterate in a consistent order, and that order can
+        // affect which cell gets collapsed next by a rounding error
+        for allowed in propagator.iter_mut().flatten() {
+            allowed.sort_unstable();
+        }
+        Solver::new(width, height, periodic, self.weights.clone(), propagator)
+    }
+
+    /// Turn a solved grid of tile indices into tiles.
+    pub fn render(&self, solution: &Grid<usize>) -> Grid<T>
+    where
+        T: Clone,
+    {
+        solution.map(|idx| self.tiles[*idx].clone())
+    }
+
+    /// Make a new model with every tile in all four rotations.
+    ///
+    /// `rotate` should rotate a tile 90 degrees clockwise. The rules are rotated along
+    /// with the tiles. Rotations that come out the same as an existing tile are merged with it.
+    pub fn with_rotations<F: FnMut(&T) -> T>(&self, mut rotate: F) -> Self
+    where
+        T: Clone + PartialEq,
+    {
+        self.with_symmetry(
+            4,
+            |tile, _| rotate(tile),
+            |dir, steps| dir.rotate_by(steps as isize),
+        )
+    }
+
+    /// Make a new model with every tile and its mirror image.
+    ///
+    /// `mirror` should flip a tile left-to-right. The rules are mirrored along
+    /// with the tiles. Mirror images that come out the same as an existing tile are merged with it.
+    pub fn with_reflections<F: FnMut(&T) -> T>(&self, mut mirror: F) -> Self
+    where
+        T: Clone + PartialEq,
+    {
+        self.with_symmetry(
+            2,
+            |tile, _| mirror(tile),
+            |dir, steps| {
+                if steps == 1 && dir.is_horizontal() {
+                    dir.flip()
+                } else {
+                    dir
+                }
+            },
+        )
+    }
+
+    /// `transform` turns variant `n` into variant `n + 1`,
+    /// and `map_dir` says where a direction goes in variant `n`.
+    fn with_symmetry<F, D>(&self, variants: usize, mut transform: F, map_dir: D) -> Self
+    where
+        T: Clone + PartialEq,
+        F: FnMut(&T, usize) -> T,
+        D: Fn(Direction4, usize) -> Direction4,
+    {
+        let mut out = TiledModel::new();
+        // ids[tile][variant] is the index of that variant in the new model
+        let mut ids = Vec::with_capacity(self.tiles.len());
+        for (tile, weight) in self.tiles.iter().zip(self.weights.iter()) {
+            let mut variant_ids = Vec::with_capacity(variants);
+            let mut variant = tile.clone();
+            for n in 0..variants {
+                let id = match out.find(&variant) {
+                    Some(id) => id,
+                    None => out.add_tile(variant.clone(), *weight),
+                };
+                variant_ids.push(id);
+                variant = transform(&variant, n);
+            }
+            ids.push(variant_ids);
+        }
+
+        for &(a, dir, b) in self.rules.iter() {
+            for (n, (&a, &b)) in ids[a].iter().zip(ids[b].iter()).enumerate() {
+                out.allow(a, map_dir(dir, n), b);
+            }
+        }
+        out
+    }
+}
+
+impl<T> Default for TiledModel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Which extra versions of the patterns in a sample an [`OverlappingModel`] should learn.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Symmetry {
+    /// Only use the patterns as they are.
+    None,
+    /// Also use the patterns rotated in all four directions.
+    Rotations,
+    /// Also use the patterns rotated in all four directions, and their mirror images.
+    All,
+}
+
+/**
+The overlapping model: it learns all the `n`x`n` patterns in a sample grid, and
+makes an output where every `n`x`n` area is one of those patterns.
+
+```
+# use cogs_gamedev::grids::{Coord, Grid, wfc::{OverlappingModel, Symmetry}};
+use rand::{rngs::StdRng, SeedableRng};
+
+// Diagonal stripes
+let sample = Grid::from_fn(3, 3, |pos| (pos.x + pos.y) % 3);
+let model = OverlappingModel::new(&sample, 2, true, Symmetry::None);
+assert_eq!(model.patterns().len(), 3);
+
+let mut solver = model.solver(12, 8, false);
+let output = model.render(&solver.solve(&mut StdRng::seed_from_u64(0), 10).unwrap(), false);
+assert_eq!((output.width(), output.height()), (12, 8));
+// It's still diagonal stripes
+for (pos, value) in output.iter_coords() {
+    if pos.x > 0 && pos.y + 1 < output.height() {
+        assert_eq!(output[Coord::new(pos.x - 1, pos.y + 1)], *value);
+    }
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct OverlappingModel<T> {
+    n: usize,
+    patterns: Vec<Grid<T>>,
+    weights: Vec<f64>,
+}
+
+impl<T: Clone + Eq + Hash> OverlappingModel<T> {
+    /// Learn the patterns of size `n`x`n` from the sample.
+    ///
+    /// If `periodic_input` is set, patterns wrap around the edges of the sample.
+    /// Patterns are weighted by how often they show up.
+    ///
+    /// Panics if `n` is 0 or the sample is too small to have any patterns.
+    pub fn new(sample: &Grid<T>, n: usize, periodic_input: bool, symmetry: Symmetry) -> Self {
+        assert_ne!(n, 0, "Patterns must be at least 1x1!");
+        let (width, height) = (sample.width(), sample.height());
+        let (max_x, max_y) = if periodic_input {
+            (width, height)
+        } else {
+            (
+                (width + 1).saturating_sub(n),
+                (height + 1).saturating_sub(n),
+            )
+        };
+        assert!(
+            max_x > 0 && max_y > 0,
+            "A {}x{} sample is too small for {}x{} patterns!",
+            width,
+            height,
+            n,
+            n
+        );
+
+        let mut patterns = Vec::new();
+        let mut weights = Vec::new();
+        let mut indices = AHashMap::new();
+        for y in 0..max_y {
+            for x in 0..max_x {
+                let pattern = Grid::from_fn(n, n, |pos| {
+                    sample[Coord::new((x + pos.x) % width, (y + pos.y) % height)].clone()
+                });
+
+                let mut variants = vec![pattern];
+                if symmetry != Symmetry::None {
+                    for _ in 0..3 {
+                        let prev = variants.last().unwrap();
+                        variants.push(Grid::from_fn(n, n, |pos| {
+                            prev[Coord::new(pos.y, n - 1 - pos.x)].clone()
+                        }));
+                    }
+                }
+                if symmetry == Symmetry::All {
+                    for idx in 0..variants.len() {
+                        let flipped = Grid::from_fn(n, n, |pos| {
+                            variants[idx][Coord::new(n - 1 - pos.x, pos.y)].clone()
+                        });
+                        variants.push(flipped);
+                    }
+                }
+
+                for variant in variants {
+                    let idx = *indices.entry(variant.clone()).or_insert_with(|| {
+                        patterns.push(variant);
+                        weights.push(0.0);
+                        patterns.len() - 1
+                    });
+                    weights[idx] += 1.0;
+                }
+            }
+        }
+
+        Self {
+            n,
+            patterns,
+            weights,
+        }
+    }
+
+    /// The size of the patterns.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Get all the patterns this learned, in index order.
+    pub fn patterns(&self) -> &[Grid<T>] {
+        &self.patterns
+    }
+
+    /// Make a solver for an output of the given size.
+    ///
+    /// If `periodic` is set, the output wraps around at the edges.
+    pub fn solver(&self, width: usize, height: usize, periodic: bool) -> Solver {
+        let mut propagator: [Vec<Vec<usize>>; 4] = Default::default();
+        for dir in Direction4::DIRECTIONS.iter() {
+            propagator[*dir as usize] = (0..self.patterns.len())
+                .map(|a| {
+                    (0..self.patterns.len())
+                        .filter(|&b| self.agrees(a, *dir, b))
+                        .collect()
+                })
+                .collect();
+        }
+
+        let (width, height) = self.wave_size(width, height, periodic);
+        Solver::new(width, height, periodic, self.weights.clone(), propagator)
+    }
+
+    /// Force the given position in the output to be the given value.
+    ///
+    /// The solver must have come from this model with the same `periodic` setting.
+    ///
+    /// Panics if the position is out of bounds.
+    pub fn pin(&self, solver: &mut Solver, pos: Coord, value: &T) {
+        let (wave_pos, offset) = if solver.is_periodic() {
+            (pos, Coord::new(0, 0))
+        } else {
+            let wave_pos = Coord::new(
+                pos.x.min(solver.width().saturating_sub(1)),
+                pos.y.min(solver.height().saturating_sub(1)),
+            );
+            (wave_pos, Coord::new(pos.x - wave_pos.x, pos.y - wave_pos.y))
+        };
+        assert!(
+            offset.x < self.n && offset.y < self.n,
+            "{} is out of bounds of the output",
+            pos
+        );
+        solver.restrict(wave_pos, |p| &self.patterns[p][offset] == value);
+    }
+
+    /// Turn a solved grid of pattern indices into the output.
+    ///
+    /// `periodic` must be the same as when the solver was made.
+    pub fn render(&self, solution: &Grid<usize>, periodic: bool) -> Grid<T> {
+        let (width, height) = (solution.width(), solution.height());
+        if periodic {
+            solution.map(|idx| self.patterns[*idx][Coord::new(0, 0)].clone())
+        } else {
+            Grid::from_fn(width + self.n - 1, height + self.n - 1, |pos| {
+                let wave_pos = Coord::new(pos.x.min(width - 1), pos.y.min(height - 1));
+                self.patterns[solution[wave_pos]]
+                    [Coord::new(pos.x - wave_pos.x, pos.y - wave_pos.y)]
+                .clone()
+            })
+        }
+    }
+
+    fn wave_size(&self, width: usize, height: usize, periodic: bool) -> (usize, usize) {
+        if periodic {
+            (width, height)
+        } else {
+            (
+                (width + 1).saturating_sub(self.n).max(1),
+                (height + 1).saturating_sub(self.n).max(1),
+            )
+        }
+    }
+
+    /// Check if pattern `b` can go one step in `dir` from pattern `a`,
+    /// which is when they match everywhere they overlap.
+    fn agrees(&self, a: usize, dir: Direction4, b: usize) -> bool {
+        let (a, b) = (&self.patterns[a], &self.patterns[b]);
+        let offset = dir.deltas();
+        a.iter_coords()
+            .all(|(pos, value)| match b.get(pos.to_icoord() - offset) {
+                Some(other) => value == other,
+                None => true,
+            })
+    }
+}
+
+#[test]
+fn rotated_pipes() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // Which sides each tile connects on, in the order of `Direction4::DIRECTIONS`
+    let rotate = |sides: &[bool; 4]| [sides[3], sides[0], sides[1], sides[2]];
+    let bases = [
+        [false, false, false, false],
+        [true, false, true, false],
+        [true, true, false, false],
+        [true, true, true, false],
+        [true, true, true, true],
+    ];
+    let mut model = TiledModel::new();
+    for base in bases.iter() {
+        model.add_tile(*base, 1.0);
+    }
+    for a in 0..bases.len() {
+        for b in 0..bases.len() {
+            for dir in Direction4::DIRECTIONS.iter() {
+                if bases[a][*dir as usize] == bases[b][dir.flip() as usize] {
+                    model.allow(a, *dir, b);
+                }
+            }
+        }
+    }
+    let model = model.with_rotations(rotate);
+    assert_eq!(model.tiles().len(), 1 + 2 + 4 + 4 + 1);
+    // Mirroring these doesn't make anything new
+    assert_eq!(
+        model
+            .with_reflections(|s| [s[0], s[3], s[2], s[1]])
+            .tiles()
+            .len(),
+        12
+    );
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut solver = model.solver(16, 16, true);
+    let pinned = model.find(&[false, true, false, true]).unwrap();
+    solver.pin(Coord::new(3, 3), pinned);
+    let map = model.render(&solver.solve(&mut rng, 20).unwrap());
+    assert_eq!(map[Coord::new(3, 3)], [false, true, false, true]);
+    for (pos, sides) in map.iter_coords() {
+        for dir in Direction4::DIRECTIONS.iter() {
+            let other = pos.to_icoord() + dir.deltas();
+            let other = ICoord::new(other.x.rem_euclid(16), other.y.rem_euclid(16));
+            assert_eq!(sides[*dir as usize], map[other][dir.flip() as usize]);
+        }
+    }
+}
+
+#[test]
+fn contradictory_pins() {
+    let mut model = TiledModel::new();
+    let a = model.add_tile('a', 1.0);
+    let b = model.add_tile('b', 1.0);
+    for dir in Direction4::DIRECTIONS.iter() {
+        model.allow(a, *dir, a);
+        model.allow(b, *dir, b);
+    }
+    let mut solver = model.solver(4, 1, false);
+    solver.pin(Coord::new(0, 0), a);
+    solver.pin(Coord::new(3, 0), b);
+    assert!(solver.solve(&mut rand::thread_rng(), 5).is_err());
+
+    solver.clear_pins();
+    let out = model.render(&solver.solve(&mut rand::thread_rng(), 5).unwrap());
+    assert!(out.iter().all(|c| *c == out[Coord::new(0, 0)]));
+}
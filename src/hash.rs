@@ -1,5 +1,7 @@
 //! Utilities for quick and dirty hashing.
 
+use crate::grids::ICoord;
+
 use ahash::AHasher;
 
 use std::hash::{Hash, Hasher};
@@ -14,6 +16,7 @@ and use it as a selector on the tile variants.
 
 This isn't guaranteed to be the same across compiles or restarts,
 but it will be the same for a given input value across one run of a program.
+If you need that, use [`stable_hashcode`] or [`hash2d`].
 
 ```
 # use cogs_gamedev::hash::hashcode;
@@ -22,11 +25,201 @@ assert_eq!(hashcode(&10i32), hashcode(&10i32));
 assert_ne!(hashcode(&10i32), hashcode(&600i32));
 
 ```
-
-[`ICoord`]: crate::grids::ICoord
 */
 pub fn hashcode<H: Hash + ?Sized>(hashee: &H) -> u64 {
     let mut hasher = AHasher::default();
     hashee.hash(&mut hasher);
     hasher.finish()
 }
+
+/**
+Get a hash for a value that's the same everywhere: across runs, across platforms,
+and across versions of this crate. Good for things that need to be reproducible,
+like tile variations in saved worlds or anything in a lockstep multiplayer game.
+
+The stability guarantee covers everything this hasher does with the data it's given:
+single integers hash the same no matter the endianness or pointer width of the platform.
+It can't cover how your type feeds itself into the hasher, though. Derived `Hash` impls
+are stable as long as you don't change the fields; the standard library's impls
+for integers, `bool`, `char`, strings, tuples, arrays, slices and `Vec`s have stayed the same
+for a long time, but aren't *promised* to stay that way forever.
+
+One catch: the standard library hashes slices, arrays and `Vec`s of integers bigger than a byte
+all at once, as their raw bytes in the platform's byte order. So they hash differently on
+big-endian platforms, and slices of `usize` or `isize` hash differently on 32-bit ones too.
+If that matters, hash each integer on its own instead, like with [`StableHasher`]:
+
+```
+# use cogs_gamedev::hash::StableHasher;
+use std::hash::{Hash, Hasher};
+
+let heights = vec![10u32, 20, 30];
+let mut hasher = StableHasher::new(0);
+heights.len().hash(&mut hasher);
+for height in heights.iter() {
+    height.hash(&mut hasher);
+}
+// This value is set in stone too
+assert_eq!(hasher.finish(), 0x20a4_2ebe_bb55_84c2);
+```
+
+```
+# use cogs_gamedev::hash::stable_hashcode;
+assert_eq!(stable_hashcode(0, &10i32), stable_hashcode(0, &10i32));
+assert_ne!(stable_hashcode(0, &10i32), stable_hashcode(1, &10i32));
+// This value is set in stone
+assert_eq!(stable_hashcode(1234, "hello"), 0x4604_4f0f_50a1_3f52);
+```
+*/
+pub fn stable_hashcode<H: Hash + ?Sized>(seed: u64, hashee: &H) -> u64 {
+    let mut hasher = StableHasher::new(seed);
+    hashee.hash(&mut hasher);
+    hasher.finish()
+}
+
+/**
+A seeded [`Hasher`] whose output is the same everywhere. See [`stable_hashcode`] for the details.
+
+You probably just want [`stable_hashcode`], but this is handy for hashing several things together
+without putting them in a tuple.
+*/
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: mix(seed ^ 0x9e37_79b9_7f4a_7c15),
+        }
+    }
+
+    fn eat(&mut self, word: u64) {
+        self.state = mix(self.state ^ word.wrapping_mul(0x8cb9_2ba7_2f3d_8dd7));
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        mix(self.state)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.eat(u64::from_le_bytes(word));
+        }
+        self.eat(bytes.len() as u64);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.eat(i as u64);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.eat(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.eat(i as u64);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.eat(i);
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.eat(i as u64);
+        self.eat((i >> 64) as u64);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.eat(i as u64);
+    }
+
+    // The default casts it to a usize, which would be different on 32-bit platforms
+    fn write_isize(&mut self, i: isize) {
+        self.eat(i as i64 as u64);
+    }
+}
+
+/**
+Quickly hash a position with a seed. The output is stable in the same way as [`stable_hashcode`],
+but it doesn't go through a [`Hasher`], so it's faster and doesn't depend on any `Hash` impls.
+
+This is the same hash the [`noise`](crate::noise) module uses.
+
+```
+# use cogs_gamedev::hash::hash2d;
+# use cogs_gamedev::grids::ICoord;
+let pos = ICoord::new(3, -4);
+assert_eq!(hash2d(99, pos), hash2d(99, pos));
+assert_ne!(hash2d(99, pos), hash2d(99, ICoord::new(-4, 3)));
+```
+*/
+pub fn hash2d(seed: u64, pos: ICoord) -> u64 {
+    hash3(seed, pos.x as i64, pos.y as i64, 0)
+}
+
+/**
+Turn a hash into a float between 0 (inclusive) and 1 (exclusive), with all 53 bits of precision.
+
+```
+# use cogs_gamedev::hash::{hash2d, hash_to_unit_f64};
+# use cogs_gamedev::grids::ICoord;
+let tile_variants = ["grass", "grass", "flowers", "rocks"];
+let pos = ICoord::new(10, 20);
+let variant = (hash_to_unit_f64(hash2d(0, pos)) * tile_variants.len() as f64) as usize;
+assert!(variant < tile_variants.len());
+
+assert_eq!(hash_to_unit_f64(0), 0.0);
+assert!(hash_to_unit_f64(u64::MAX) < 1.0);
+```
+*/
+pub fn hash_to_unit_f64(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Hash three coordinates with a seed.
+pub(crate) fn hash3(seed: u64, x: i64, y: i64, z: i64) -> u64 {
+    let mut h = mix(seed ^ 0x9e37_79b9_7f4a_7c15);
+    h = mix(h ^ (x as u64).wrapping_mul(0x8cb9_2ba7_2f3d_8dd7));
+    h = mix(h ^ (y as u64).wrapping_mul(0xa24b_aed4_963e_e407));
+    mix(h ^ (z as u64).wrapping_mul(0x9fb2_1c65_1e98_df25))
+}
+
+/// The SplitMix64 finalizer.
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[test]
+fn golden_values() {
+    // These must never change between versions!
+    let hashes = [
+        stable_hashcode(0, &10i32),
+        stable_hashcode(0, &-10isize),
+        stable_hashcode(42, "The quick brown fox jumps over the lazy dog"),
+        stable_hashcode(7, &(1u8, 'c', true, (5u64, 5u64, 5u64))),
+        stable_hashcode(7, &ICoord::new(3, -4)),
+        hash2d(0, ICoord::new(0, 0)),
+        hash2d(123456789, ICoord::new(3, -4)),
+    ];
+    assert_eq!(
+        hashes,
+        [
+            0x803c_158f_0bc7_fd43,
+            0x1bfb_e1db_dc8b_e9ef,
+            0xd5a6_148e_96e0_add5,
+            0x8634_2ed8_2a11_208b,
+            0xc2e4_a7ae_8d58_febd,
+            0x1957_a760_4e21_5178,
+            0xef82_a705_ddaf_2d70,
+        ]
+    );
+    assert_eq!(hash_to_unit_f64(hashes[0]), 0.5009168123467872);
+}
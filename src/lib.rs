@@ -3,3 +3,4 @@ pub mod controls;
 pub mod ease;
 pub mod grids;
 pub mod hash;
+pub mod noise;
@@ -0,0 +1,661 @@
+/*!
+Coherent noise: random-looking values that change smoothly as you move around.
+Good for terrain, clouds, wobbly animations, and so on.
+
+Everything here is seeded, and the output is the same on every platform and every run
+for a given seed and input. (This is unlike [`hashcode`](crate::hash::hashcode).)
+
+All the noise types implement [`Noise`], so you can stack them together:
+
+```
+# use cogs_gamedev::noise::{Fractal, FractalKind, Noise, Perlin, Simplex, Warp};
+# use cogs_gamedev::grids::IRect;
+let terrain = Warp::new(
+    Fractal::new(Perlin::new(1234), FractalKind::Fbm),
+    Simplex::new(5678),
+    0.5,
+);
+let heightmap = terrain.fill(IRect::new(-16, -16, 32, 32), 0.05);
+assert!(heightmap.iter().all(|h| (-1.0..=1.0).contains(h)));
+```
+*/
+
+use crate::{
+    grids::{Grid, IRect},
+    hash::{hash3, hash_to_unit_f64, mix},
+};
+
+/// Something that can make noise in 2 and 3 dimensions.
+pub trait Noise {
+    /// Sample the noise at a point in 2D space.
+    fn sample2(&self, x: f64, y: f64) -> f64;
+
+    /// Sample the noise at a point in 3D space.
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64;
+
+    /// Make a grid the same size as `area` and fill it with samples of the noise.
+    ///
+    /// Each cell's position in `area` is multiplied by `frequency` to get where it's sampled;
+    /// smaller frequencies zoom in and make smoother results.
+    ///
+    /// ```
+    /// # use cogs_gamedev::noise::{Noise, Value};
+    /// # use cogs_gamedev::grids::{Coord, IRect};
+    /// let noise = Value::new(0);
+    /// let grid = noise.fill(IRect::new(10, 20, 4, 3), 0.5);
+    /// assert_eq!(grid[Coord::new(2, 1)], noise.sample2(6.0, 10.5));
+    /// ```
+    fn fill(&self, area: IRect, frequency: f64) -> Grid<f64> {
+        Grid::from_fn(area.width, area.height, |pos| {
+            self.sample2(
+                (area.left + pos.x as isize) as f64 * frequency,
+                (area.top + pos.y as isize) as f64 * frequency,
+            )
+        })
+    }
+}
+
+impl<N: Noise + ?Sized> Noise for &N {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        (**self).sample2(x, y)
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).sample3(x, y, z)
+    }
+}
+
+impl<N: Noise + ?Sized> Noise for Box<N> {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        (**self).sample2(x, y)
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).sample3(x, y, z)
+    }
+}
+
+/// Classic [Perlin noise](https://en.wikipedia.org/wiki/Perlin_noise).
+///
+/// Outputs are between -1 and 1, and are always 0 on integer coordinates.
+/// It has a bit of a tendency to line up along the axes; [`Simplex`] doesn't.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Perlin {
+    pub seed: u64,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Noise for Perlin {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (ix, iy) = (x0 as i64, y0 as i64);
+
+        let corner = |dx: i64, dy: i64| {
+            let h = hash3(self.seed, ix + dx, iy + dy, 0);
+            grad2(h, fx - dx as f64, fy - dy as f64)
+        };
+        let (u, v) = (fade(fx), fade(fy));
+        let out = lerp(
+            v,
+            lerp(u, corner(0, 0), corner(1, 0)),
+            lerp(u, corner(0, 1), corner(1, 1)),
+        );
+        // The most it can get to with unit gradients is sqrt(2)/2
+        (out * std::f64::consts::SQRT_2).clamp(-1.0, 1.0)
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (fx, fy, fz) = (x - x0, y - y0, z - z0);
+        let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+
+        let corner = |dx: i64, dy: i64, dz: i64| {
+            let h = hash3(self.seed, ix + dx, iy + dy, iz + dz);
+            grad3(h, fx - dx as f64, fy - dy as f64, fz - dz as f64)
+        };
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+        let near = lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        );
+        let far = lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        );
+        lerp(w, near, far).clamp(-1.0, 1.0)
+    }
+}
+
+/// Simplex-style gradient noise, in the same family as OpenSimplex.
+///
+/// It's sampled off a triangular (or tetrahedral) lattice instead of a square one,
+/// so it doesn't have [`Perlin`]'s tendency to line up along the axes.
+/// Outputs are between -1 and 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Simplex {
+    pub seed: u64,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Noise for Simplex {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        // (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6
+        const SKEW: f64 = 0.366_025_403_784_438_6;
+        const UNSKEW: f64 = 0.211_324_865_405_187_1;
+
+        let s = (x + y) * SKEW;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+        let t = (i + j) * UNSKEW;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+        let (ix, iy) = (i as i64, j as i64);
+
+        // Which triangle of the square are we in?
+        let (di, dj) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let corners = [
+            (0, 0, x0, y0),
+            (di, dj, x0 - di as f64 + UNSKEW, y0 - dj as f64 + UNSKEW),
+            (1, 1, x0 - 1.0 + 2.0 * UNSKEW, y0 - 1.0 + 2.0 * UNSKEW),
+        ];
+
+        let mut out = 0.0;
+        for &(dx, dy, cx, cy) in corners.iter() {
+            let falloff = 0.5 - cx * cx - cy * cy;
+            if falloff > 0.0 {
+                // Not `powi`, which doesn't promise to be the same everywhere
+                let falloff = falloff * falloff;
+                let h = hash3(self.seed, ix + dx, iy + dy, 0);
+                out += falloff * falloff * grad2(h, cx, cy);
+            }
+        }
+        (out * 99.0).clamp(-1.0, 1.0)
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        const SKEW: f64 = 1.0 / 3.0;
+        const UNSKEW: f64 = 1.0 / 6.0;
+
+        let s = (x + y + z) * SKEW;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * UNSKEW;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+        let (ix, iy, iz) = (i as i64, j as i64, k as i64);
+
+        // Which of the six tetrahedra of the cube are we in?
+        let (first, second) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let mut out = 0.0;
+        for (n, &(dx, dy, dz)) in [(0, 0, 0), first, second, (1, 1, 1)].iter().enumerate() {
+            let (cx, cy, cz) = (
+                x0 - dx as f64 + n as f64 * UNSKEW,
+                y0 - dy as f64 + n as f64 * UNSKEW,
+                z0 - dz as f64 + n as f64 * UNSKEW,
+            );
+            let falloff = 0.6 - cx * cx - cy * cy - cz * cz;
+            if falloff > 0.0 {
+                let h = hash3(self.seed, ix + dx, iy + dy, iz + dz);
+                let falloff = falloff * falloff;
+                out += falloff * falloff * grad3(h, cx, cy, cz);
+            }
+        }
+        (out * 32.0).clamp(-1.0, 1.0)
+    }
+}
+
+/// Value noise: every integer coordinate gets a random value, and everything in between
+/// is smoothly interpolated.
+///
+/// It's blobbier than gradient noise like [`Perlin`]. Outputs are between -1 and 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Value {
+    pub seed: u64,
+}
+
+impl Value {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl Noise for Value {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (ix, iy) = (x0 as i64, y0 as i64);
+        let corner =
+            |dx: i64, dy: i64| hash_to_unit_f64(hash3(self.seed, ix + dx, iy + dy, 0)) * 2.0 - 1.0;
+
+        let (u, v) = (fade(x - x0), fade(y - y0));
+        lerp(
+            v,
+            lerp(u, corner(0, 0), corner(1, 0)),
+            lerp(u, corner(0, 1), corner(1, 1)),
+        )
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (ix, iy, iz) = (x0 as i64, y0 as i64, z0 as i64);
+        let corner = |dx: i64, dy: i64, dz: i64| {
+            hash_to_unit_f64(hash3(self.seed, ix + dx, iy + dy, iz + dz)) * 2.0 - 1.0
+        };
+
+        let (u, v, w) = (fade(x - x0), fade(y - y0), fade(z - z0));
+        let near = lerp(
+            v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+        );
+        let far = lerp(
+            v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+        );
+        lerp(w, near, far)
+    }
+}
+
+/// How to measure distance for [`Worley`] noise.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WorleyDistance {
+    /// Straight-line distance. Makes round cells.
+    Euclidean,
+    /// Taxicab distance. Makes diamond-y cells.
+    Manhattan,
+    /// The biggest distance on any axis. Makes square-y cells.
+    Chebyshev,
+}
+
+/// What [`Worley`] noise outputs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WorleyOutput {
+    /// The distance to the closest point.
+    F1,
+    /// The distance to the second-closest point.
+    F2,
+    /// The difference between the distances to the two closest points.
+    /// This is 0 on the borders between cells.
+    F2MinusF1,
+}
+
+/// [Worley noise](https://en.wikipedia.org/wiki/Worley_noise), aka cellular noise.
+///
+/// Every integer cell has a random point somewhere in it, and the output is based on the distance
+/// to the nearest points.
+///
+/// Unlike the other noises, the output isn't between -1 and 1; it's a distance in units of cells,
+/// so it's always positive and usually less than about 1.5.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Worley {
+    pub seed: u64,
+    pub distance: WorleyDistance,
+    pub output: WorleyOutput,
+}
+
+impl Worley {
+    /// Make new Worley noise with Euclidean distance that outputs the distance to the closest point.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            distance: WorleyDistance::Euclidean,
+            output: WorleyOutput::F1,
+        }
+    }
+
+    fn measure(&self, dx: f64, dy: f64, dz: f64) -> f64 {
+        let (dx, dy, dz) = (dx.abs(), dy.abs(), dz.abs());
+        match self.distance {
+            WorleyDistance::Euclidean => (dx * dx + dy * dy + dz * dz).sqrt(),
+            WorleyDistance::Manhattan => dx + dy + dz,
+            WorleyDistance::Chebyshev => dx.max(dy).max(dz),
+        }
+    }
+
+    fn finish(&self, f1: f64, f2: f64) -> f64 {
+        match self.output {
+            WorleyOutput::F1 => f1,
+            WorleyOutput::F2 => f2,
+            WorleyOutput::F2MinusF1 => f2 - f1,
+        }
+    }
+}
+
+impl Noise for Worley {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        let (ix, iy) = (x.floor() as i64, y.floor() as i64);
+        let (mut f1, mut f2) = (f64::INFINITY, f64::INFINITY);
+        for cy in iy - 1..=iy + 1 {
+            for cx in ix - 1..=ix + 1 {
+                let h = hash3(self.seed, cx, cy, 0);
+                let (px, py) = (
+                    cx as f64 + hash_to_unit_f64(h),
+                    cy as f64 + hash_to_unit_f64(mix(h)),
+                );
+                let dist = self.measure(px - x, py - y, 0.0);
+                if dist < f1 {
+                    f2 = f1;
+                    f1 = dist;
+                } else if dist < f2 {
+                    f2 = dist;
+                }
+            }
+        }
+        self.finish(f1, f2)
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (ix, iy, iz) = (x.floor() as i64, y.floor() as i64, z.floor() as i64);
+        let (mut f1, mut f2) = (f64::INFINITY, f64::INFINITY);
+        for cz in iz - 1..=iz + 1 {
+            for cy in iy - 1..=iy + 1 {
+                for cx in ix - 1..=ix + 1 {
+                    let h = hash3(self.seed, cx, cy, cz);
+                    let h2 = mix(h);
+                    let (px, py, pz) = (
+                        cx as f64 + hash_to_unit_f64(h),
+                        cy as f64 + hash_to_unit_f64(h2),
+                        cz as f64 + hash_to_unit_f64(mix(h2)),
+                    );
+                    let dist = self.measure(px - x, py - y, pz - z);
+                    if dist < f1 {
+                        f2 = f1;
+                        f1 = dist;
+                    } else if dist < f2 {
+                        f2 = dist;
+                    }
+                }
+            }
+        }
+        self.finish(f1, f2)
+    }
+}
+
+/// How a [`Fractal`] combines its octaves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FractalKind {
+    /// Fractal Brownian motion: just add them up. The standard cloudy look.
+    Fbm,
+    /// Flip the valleys of each octave into sharp ridges, like mountain ranges.
+    Ridged,
+    /// Fold each octave's valleys up into rounded bumps, like billowing clouds.
+    Billow,
+}
+
+/**
+Layers several octaves of another noise on top of each other, each one at a higher frequency
+and lower amplitude than the last, for detail at lots of scales.
+
+If the source's outputs are between -1 and 1, so are these.
+
+```
+# use cogs_gamedev::noise::{Fractal, FractalKind, Noise, Perlin};
+let mut mountains = Fractal::new(Perlin::new(42), FractalKind::Ridged);
+mountains.octaves = 6;
+let height = mountains.sample2(12.5, -3.25);
+assert!((-1.0..=1.0).contains(&height));
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fractal<N> {
+    pub source: N,
+    pub kind: FractalKind,
+    /// How many layers to add up.
+    pub octaves: usize,
+    /// The frequency of the first octave.
+    pub frequency: f64,
+    /// How much the frequency is multiplied by each octave.
+    pub lacunarity: f64,
+    /// How much the amplitude is multiplied by each octave.
+    pub gain: f64,
+}
+
+impl<N: Noise> Fractal<N> {
+    /// Make a new fractal with 4 octaves, frequency 1, lacunarity 2, and gain 0.5.
+    pub fn new(source: N, kind: FractalKind) -> Self {
+        Self {
+            source,
+            kind,
+            octaves: 4,
+            frequency: 1.0,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    fn combine<F: FnMut(usize, f64) -> f64>(&self, mut sample: F) -> f64 {
+        let mut out = 0.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut frequency = self.frequency;
+        for octave in 0..self.octaves {
+            let n = sample(octave, frequency);
+            let n = match self.kind {
+                FractalKind::Fbm => n,
+                FractalKind::Ridged => {
+                    let ridge = 1.0 - n.abs();
+                    ridge * ridge * 2.0 - 1.0
+                }
+                FractalKind::Billow => n.abs() * 2.0 - 1.0,
+            };
+            out += n * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= self.gain;
+            frequency *= self.lacunarity;
+        }
+        if total_amplitude > 0.0 {
+            out / total_amplitude
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Each octave is shifted over by this much so they don't all line up at the origin.
+const OCTAVE_OFFSET: f64 = 71.137;
+
+impl<N: Noise> Noise for Fractal<N> {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        self.combine(|octave, frequency| {
+            let offset = octave as f64 * OCTAVE_OFFSET;
+            self.source
+                .sample2(x * frequency + offset, y * frequency - offset)
+        })
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.combine(|octave, frequency| {
+            let offset = octave as f64 * OCTAVE_OFFSET;
+            self.source.sample3(
+                x * frequency + offset,
+                y * frequency - offset,
+                z * frequency + offset,
+            )
+        })
+    }
+}
+
+/**
+[Domain warping](https://iquilezles.org/articles/warp/): distorts the input coordinates
+of one noise with another noise before sampling it, for swirly, organic shapes.
+
+The output is the output of the source noise.
+
+```
+# use cogs_gamedev::noise::{Noise, Perlin, Value, Warp};
+let straight = Value::new(3);
+let swirly = Warp::new(straight, Perlin::new(4), 2.0);
+assert_ne!(straight.sample2(0.5, 0.5), swirly.sample2(0.5, 0.5));
+// No strength means no warping
+let unwarped = Warp::new(straight, Perlin::new(4), 0.0);
+assert_eq!(straight.sample2(0.5, 0.5), unwarped.sample2(0.5, 0.5));
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warp<N, W> {
+    pub source: N,
+    /// The noise used to offset each coordinate.
+    pub warp: W,
+    /// How far the coordinates can be pushed.
+    pub strength: f64,
+}
+
+impl<N: Noise, W: Noise> Warp<N, W> {
+    pub fn new(source: N, warp: W, strength: f64) -> Self {
+        Self {
+            source,
+            warp,
+            strength,
+        }
+    }
+}
+
+// Sample the warp noise at different places for each axis so they don't all move together
+const WARP_OFFSETS: [(f64, f64, f64); 3] = [(0.0, 0.0, 0.0), (5.2, 1.3, 9.7), (-8.3, 2.8, -4.1)];
+
+impl<N: Noise, W: Noise> Noise for Warp<N, W> {
+    fn sample2(&self, x: f64, y: f64) -> f64 {
+        let offset =
+            |(ox, oy, _): (f64, f64, f64)| self.warp.sample2(x + ox, y + oy) * self.strength;
+        self.source
+            .sample2(x + offset(WARP_OFFSETS[0]), y + offset(WARP_OFFSETS[1]))
+    }
+
+    fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let offset = |(ox, oy, oz): (f64, f64, f64)| {
+            self.warp.sample3(x + ox, y + oy, z + oz) * self.strength
+        };
+        self.source.sample3(
+            x + offset(WARP_OFFSETS[0]),
+            y + offset(WARP_OFFSETS[1]),
+            z + offset(WARP_OFFSETS[2]),
+        )
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad2(hash: u64, x: f64, y: f64) -> f64 {
+    const DIAG: f64 = std::f64::consts::FRAC_1_SQRT_2;
+    let (gx, gy) = match hash >> 61 {
+        0 => (1.0, 0.0),
+        1 => (-1.0, 0.0),
+        2 => (0.0, 1.0),
+        3 => (0.0, -1.0),
+        4 => (DIAG, DIAG),
+        5 => (-DIAG, DIAG),
+        6 => (DIAG, -DIAG),
+        _ => (-DIAG, -DIAG),
+    };
+    gx * x + gy * y
+}
+
+fn grad3(hash: u64, x: f64, y: f64, z: f64) -> f64 {
+    // The 12 edges of a cube, and 4 of them again to get to 16
+    match hash >> 60 {
+        0 | 12 => x + y,
+        1 | 13 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 14 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+#[test]
+fn golden_values() {
+    // If these ever change, everyone's worlds change. Don't let them change.
+    let golden2 = [
+        Perlin::new(0).sample2(0.3, 0.7),
+        Simplex::new(0).sample2(0.3, 0.7),
+        Value::new(0).sample2(0.3, 0.7),
+        Worley::new(0).sample2(0.3, 0.7),
+        Fractal::new(Perlin::new(1), FractalKind::Fbm).sample2(-12.1, 4.4),
+    ];
+    let golden3 = [
+        Perlin::new(0).sample3(0.3, 0.7, -1.9),
+        Simplex::new(0).sample3(0.3, 0.7, -1.9),
+        Value::new(0).sample3(0.3, 0.7, -1.9),
+        Worley::new(0).sample3(0.3, 0.7, -1.9),
+    ];
+    assert_eq!(
+        golden2,
+        [
+            -0.37437696597363124,
+            -0.7863484644815819,
+            -0.39846522110591476,
+            0.41868130839806506,
+            -0.04669356083649919,
+        ]
+    );
+    assert_eq!(
+        golden3,
+        [
+            0.21377953142791675,
+            0.48428051199999983,
+            0.6707299597362285,
+            0.8036389194304502,
+        ]
+    );
+}
+
+#[test]
+fn ranges() {
+    let noises: [Box<dyn Noise>; 5] = [
+        Box::new(Perlin::new(7)),
+        Box::new(Simplex::new(7)),
+        Box::new(Value::new(7)),
+        Box::new(Fractal::new(Simplex::new(7), FractalKind::Ridged)),
+        Box::new(Fractal::new(Value::new(7), FractalKind::Billow)),
+    ];
+    for noise in noises.iter() {
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+        for i in 0..4000 {
+            let (x, y, z) = (i as f64 * 0.173, i as f64 * -0.291, i as f64 * 0.057);
+            for n in [noise.sample2(x, y), noise.sample3(x, y, z)].iter() {
+                min = min.min(*n);
+                max = max.max(*n);
+            }
+        }
+        assert!(min >= -1.0 && max <= 1.0);
+        // and it actually uses a decent part of the range
+        assert!(min < -0.3 && max > 0.3, "{} {}", min, max);
+    }
+}